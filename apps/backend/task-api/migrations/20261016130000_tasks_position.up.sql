@@ -0,0 +1,13 @@
+-- Manual ordering of a user's tasks; lower positions come first
+ALTER TABLE "tasks" ADD COLUMN IF NOT EXISTS "position" INTEGER NOT NULL DEFAULT 0;
+
+-- Existing tasks keep their creation order
+UPDATE "tasks" t
+SET "position" = ordered.rn
+FROM (
+    SELECT id, ROW_NUMBER() OVER (PARTITION BY user_id ORDER BY created_at, id) AS rn
+    FROM "tasks"
+) ordered
+WHERE t.id = ordered.id;
+
+CREATE INDEX IF NOT EXISTS "idx_tasks_user_id_position" ON "tasks"("user_id", "position");
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS "idx_tasks_tenant_id_user_id";
+
+ALTER TABLE "tasks" DROP COLUMN IF EXISTS "tenant_id";
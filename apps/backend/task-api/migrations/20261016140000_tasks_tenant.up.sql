@@ -0,0 +1,4 @@
+-- Tenant (organization) a task belongs to; tasks created before tenancy land in 'default'
+ALTER TABLE "tasks" ADD COLUMN IF NOT EXISTS "tenant_id" VARCHAR(100) NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS "idx_tasks_tenant_id_user_id" ON "tasks"("tenant_id", "user_id");
@@ -0,0 +1 @@
+ALTER TABLE "tasks" ALTER COLUMN "name" TYPE VARCHAR(100) USING left("name", 100);
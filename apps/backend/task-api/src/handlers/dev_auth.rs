@@ -0,0 +1,63 @@
+//! `DEV_MODE`: run user routes without Keycloak tokens during local development.
+//!
+//! The Keycloak layer on user routes switches to `PassthroughMode::Pass`, and
+//! [`inject_dev_user`] acts as [`DEV_USER_ID`] whenever no valid token was
+//! decoded. Admin routes still require a real token. `Config::init` refuses
+//! `DEV_MODE` in release builds.
+
+use crate::models::{role::Role, state::AppState};
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use axum_keycloak_auth::{
+    decode::{Audience, Email, KeycloakToken, Profile, ProfileAndEmail},
+    role::KeycloakRole,
+};
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+/// The user every unauthenticated request acts as in `DEV_MODE`
+pub const DEV_USER_ID: Uuid = Uuid::from_u128(1);
+
+/// A decoded token as the Keycloak layer would hand it to handlers
+pub fn local_token(subject: Uuid, roles: &[Role], issuer: &str) -> KeycloakToken<Role> {
+    let now = time::OffsetDateTime::now_utc();
+    KeycloakToken {
+        expires_at: now + time::Duration::minutes(5),
+        issued_at: now,
+        jwt_id: Uuid::new_v4().to_string(),
+        issuer: issuer.to_string(),
+        audience: Audience::Single(issuer.to_string()),
+        subject: subject.to_string(),
+        authorized_party: issuer.to_string(),
+        roles: roles
+            .iter()
+            .cloned()
+            .map(|role| KeycloakRole::Realm { role })
+            .collect(),
+        extra: ProfileAndEmail {
+            profile: Profile {
+                preferred_username: format!("user-{}", subject),
+                given_name: None,
+                family_name: None,
+                full_name: None,
+            },
+            email: Email {
+                email: format!("{}@example.com", subject),
+                email_verified: true,
+            },
+        },
+    }
+}
+
+pub async fn inject_dev_user(State(state): State<Arc<AppState>>, mut req: Request, next: Next) -> Response {
+    if state.config.dev_mode && req.extensions().get::<KeycloakToken<Role>>().is_none() {
+        debug!(subject = %DEV_USER_ID, path = %req.uri().path(), "DEV_MODE: acting as the dev user");
+        req.extensions_mut()
+            .insert(local_token(DEV_USER_ID, &[Role::User], "dev-mode"));
+    }
+    next.run(req).await
+}
@@ -0,0 +1,170 @@
+use crate::handlers::ownership::{current_user_id, Tenant};
+use crate::models::{
+    error::AppError,
+    query::TaskExportQuery,
+    response::TaskResponse,
+    role::Role,
+    state::AppState,
+    task::Task,
+};
+use crate::queries;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Extension, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::{Stream, TryStreamExt};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::{Arc, Mutex};
+use tracing::{debug, error, info};
+use uuid::Uuid;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+const CSV_HEADER: [&str; 8] = [
+    "id",
+    "name",
+    "description",
+    "user_id",
+    "position",
+    "client_id",
+    "created_at",
+    "updated_at",
+];
+
+#[utoipa::path(
+    get,
+    path = "/api/tasks/export",
+    tag = "tasks",
+    params(TaskExportQuery),
+    responses(
+        (status = 200, description = "All of the caller's tasks as a CSV or JSON attachment", content_type = "text/csv", body = String),
+        (status = 400, description = "Unsupported export format"),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn export_tasks(
+    Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    Tenant(tenant): Tenant,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<TaskExportQuery>,
+) -> Result<Response, AppError> {
+    let user_id = current_user_id(&token)?;
+    let format = query.format.as_deref().unwrap_or("csv").to_ascii_lowercase();
+
+    debug!(
+        format = %format,
+        "Exporting tasks"
+    );
+
+    // Rows are streamed straight from the cursor so memory stays flat for large exports
+    let (content_type, filename, body) = match format.as_str() {
+        "csv" => ("text/csv; charset=utf-8", "tasks.csv", Body::from_stream(csv_stream(state.db_read.clone(), user_id, tenant))),
+        "json" => ("application/json", "tasks.json", Body::from_stream(json_stream(state.db_read.clone(), user_id, tenant))),
+        other => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "fail",
+                    "error": format!("Unsupported export format '{}', expected csv or json", other)
+                })),
+            )
+                .into());
+        }
+    };
+
+    info!(
+        format = %format,
+        "Task export started"
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+fn csv_stream(db: PgPool, user_id: Uuid, tenant: String) -> impl Stream<Item = Result<Bytes, BoxError>> {
+    async_stream::try_stream! {
+        // One writer for the whole export; each record is flushed into `chunks` and handed off as it is written
+        let chunks = ChunkBuffer::default();
+        let mut writer = csv::Writer::from_writer(chunks.clone());
+        writer.write_record(CSV_HEADER)?;
+        writer.flush()?;
+        yield chunks.take();
+
+        let mut rows = sqlx::query_as::<_, Task>(queries::EXPORT_TASKS).bind(user_id).bind(&tenant).fetch(&db);
+        while let Some(task) = rows.try_next().await.inspect_err(|e| log_stream_error(user_id, e))? {
+            writer.write_record([
+                task.id.to_string(),
+                task.name,
+                task.description.unwrap_or_default(),
+                task.user_id.to_string(),
+                task.position.to_string(),
+                task.client_id.unwrap_or_default(),
+                task.created_at.to_rfc3339(),
+                task.updated_at.to_rfc3339(),
+            ])?;
+            writer.flush()?;
+            yield chunks.take();
+        }
+    }
+}
+
+fn json_stream(db: PgPool, user_id: Uuid, tenant: String) -> impl Stream<Item = Result<Bytes, BoxError>> {
+    async_stream::try_stream! {
+        yield Bytes::from_static(b"[");
+
+        let mut rows = sqlx::query_as::<_, Task>(queries::EXPORT_TASKS).bind(user_id).bind(&tenant).fetch(&db);
+        let mut first = true;
+        while let Some(task) = rows.try_next().await.inspect_err(|e| log_stream_error(user_id, e))? {
+            let mut chunk = if first { Vec::new() } else { b",".to_vec() };
+            serde_json::to_writer(&mut chunk, &TaskResponse::from(task))?;
+            first = false;
+            yield Bytes::from(chunk);
+        }
+
+        yield Bytes::from_static(b"]");
+    }
+}
+
+// Bytes the CSV writer has flushed but the stream hasn't sent yet
+#[derive(Clone, Default)]
+struct ChunkBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl ChunkBuffer {
+    fn take(&self) -> Bytes {
+        Bytes::from(std::mem::take(&mut *self.0.lock().unwrap()))
+    }
+}
+
+impl std::io::Write for ChunkBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Headers are already sent by the time the cursor fails, so all we can do is log and cut the body short.
+// The body streams after the handler's user span has closed, hence the explicit user_id.
+fn log_stream_error(user_id: Uuid, e: &sqlx::Error) {
+    error!(
+        user_id = %user_id,
+        error = %e,
+        "Failed to stream tasks for export"
+    );
+}
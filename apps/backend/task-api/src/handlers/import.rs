@@ -0,0 +1,231 @@
+use crate::handlers::{
+    ownership::{current_user_id, Tenant},
+    transaction::Tx,
+};
+use crate::models::{
+    error::AppError,
+    query::TaskImportQuery,
+    response::{success, ImportRowError, ImportSummary, Success},
+    role::Role,
+    state::AppState,
+    task::CreateTaskSchema,
+};
+use crate::queries;
+use axum::{
+    extract::{Extension, Multipart, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+#[utoipa::path(
+    post,
+    path = "/api/tasks/import",
+    tag = "tasks",
+    params(TaskImportQuery),
+    request_body(content_type = "multipart/form-data", description = "CSV in a `file` field with a `name` column and an optional `description` column"),
+    responses(
+        (status = 200, description = "Valid rows imported; invalid rows listed by line", body = Success<ImportSummary>),
+        (status = 400, description = "Malformed upload, more than MAX_BATCH_ITEMS rows, or invalid rows in strict mode"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Upload too large"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn import_tasks(
+    Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    mut tx: Tx,
+    Query(query): Query<TaskImportQuery>,
+    mut multipart: Multipart,
+) -> Result<Json<Success<ImportSummary>>, AppError> {
+    let user_id = current_user_id(&token)?;
+    let strict = query.strict.unwrap_or(false);
+
+    let upload = read_file_field(&mut multipart).await?;
+    let (rows, mut errors) = parse_rows(
+        &upload,
+        state.config.task_name_max_chars,
+        state.config.task_description_max_chars,
+        state.config.max_batch_items,
+    )?;
+
+    debug!(
+        valid_rows = rows.len(),
+        invalid_rows = errors.len(),
+        strict,
+        "Parsed task import"
+    );
+
+    // Same lock as create_task, so the limit holds against concurrent creates and imports
+    sqlx::query(queries::LOCK_USER_TASKS)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .execute(tx.conn().await?)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to import tasks"))?;
+
+    // Rows past MAX_TASKS_PER_USER are reported like any other invalid row
+    let mut rows = rows;
+    if let Some(max_tasks) = state.config.max_tasks_per_user {
+        let task_count: i64 = sqlx::query_scalar(queries::USER_TASK_COUNT)
+            .bind(user_id)
+            .bind(&tenant.0)
+            .fetch_one(tx.conn().await?)
+            .await
+            .map_err(|e| AppError::db(&state.db, e, "Failed to import tasks"))?;
+        let remaining = usize::try_from(max_tasks - task_count).unwrap_or(0);
+        if rows.len() > remaining {
+            errors.extend(rows.split_off(remaining).into_iter().map(|(line, _)| ImportRowError {
+                line,
+                error: "task limit reached".to_string(),
+            }));
+            errors.sort_by_key(|e| e.line);
+        }
+    }
+
+    if strict && !errors.is_empty() {
+        warn!(
+            invalid_rows = errors.len(),
+            "Strict task import rejected"
+        );
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "fail",
+                "error": "Import rejected: some rows are invalid",
+                "details": errors
+            })),
+        )
+            .into());
+    }
+
+    for (_, task) in &rows {
+        sqlx::query(queries::INSERT_TASK)
+            .bind(&task.name)
+            .bind(&task.description)
+            .bind(user_id)
+            .bind(&tenant.0)
+            .bind(&task.client_id)
+            .execute(tx.conn().await?)
+            .await
+            .map_err(|e| AppError::db(&state.db, e, "Failed to import tasks"))?;
+    }
+
+    // Committed by the transaction middleware once this returns 2xx
+    info!(
+        imported = rows.len(),
+        skipped = errors.len(),
+        "Tasks imported"
+    );
+
+    Ok(success(ImportSummary { imported: rows.len(), errors }))
+}
+
+async fn read_file_field(multipart: &mut Multipart) -> Result<Vec<u8>, AppError> {
+    // Size and framing errors carry their own status, e.g. 413 once IMPORT_MAX_BYTES is exceeded
+    let multipart_error = |e: axum::extract::multipart::MultipartError| -> AppError {
+        (
+            e.status(),
+            Json(json!({
+                "status": "fail",
+                "error": "Invalid upload",
+                "details": e.body_text()
+            })),
+        )
+            .into()
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() == Some("file") {
+            return Ok(field.bytes().await.map_err(multipart_error)?.to_vec());
+        }
+    }
+
+    Err((
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "status": "fail",
+            "error": "Missing 'file' field in multipart upload"
+        })),
+    )
+        .into())
+}
+
+type ParsedRows = (Vec<(u64, CreateTaskSchema)>, Vec<ImportRowError>);
+
+fn parse_rows(
+    upload: &[u8],
+    name_max_chars: usize,
+    description_max_chars: usize,
+    max_rows: usize,
+) -> Result<ParsedRows, AppError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(upload);
+
+    let headers = reader.headers().cloned().unwrap_or_default();
+    let column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+    let Some(name_column) = column("name") else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "fail",
+                "error": "CSV must have a header row with a 'name' column"
+            })),
+        )
+            .into());
+    };
+    let description_column = column("description");
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+    // Counts invalid rows too, and stops reading as soon as the upload is known to be too long
+    for (index, record) in reader.records().enumerate() {
+        if index == max_rows {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "fail",
+                    "error": format!("CSV must contain at most {} rows", max_rows)
+                })),
+            )
+                .into());
+        }
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(ImportRowError {
+                    line: e.position().map(|p| p.line()).unwrap_or_default(),
+                    error: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+
+        let description = description_column
+            .and_then(|i| record.get(i))
+            .filter(|d| !d.is_empty())
+            .map(String::from);
+        let mut task = CreateTaskSchema {
+            name: record.get(name_column).unwrap_or_default().to_string(),
+            description,
+            client_id: None,
+        };
+        match task.validate(name_max_chars, description_max_chars) {
+            Ok(()) => rows.push((line, task)),
+            Err(error) => errors.push(ImportRowError { line, error }),
+        }
+    }
+
+    Ok((rows, errors))
+}
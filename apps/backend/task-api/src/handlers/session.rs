@@ -0,0 +1,115 @@
+use crate::handlers::ownership::current_user_id;
+use crate::models::{
+    error::AppError,
+    response::{message, success, ActionMessage, SessionResponse, Success, TokenValidity},
+    role::Role,
+    state::AppState,
+};
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    Json,
+};
+use axum_keycloak_auth::decode::KeycloakToken;
+use chrono::DateTime;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/sessions",
+    tag = "auth",
+    responses(
+        (status = 200, description = "The caller's active sessions; empty if there are none", body = Success<Vec<SessionResponse>>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn list_sessions(
+    Extension(token): Extension<KeycloakToken<Role>>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Success<Vec<SessionResponse>>>, AppError> {
+    let user_id = current_user_id(&token)?;
+    debug!("Listing sessions from Keycloak");
+
+    let sessions: Vec<SessionResponse> = state
+        .keycloak
+        .list_user_sessions(user_id)
+        .await?
+        .into_iter()
+        .map(|s| s.into_response())
+        .collect();
+
+    debug!(session_count = sessions.len(), "Sessions retrieved from Keycloak");
+    Ok(success(sessions))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/auth/sessions/{session_id}",
+    tag = "auth",
+    params(
+        ("session_id" = String, Path, description = "Keycloak session ID")
+    ),
+    responses(
+        (status = 200, description = "Session revoked", body = Success<ActionMessage>),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Not one of the caller's sessions"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn revoke_session(
+    Extension(token): Extension<KeycloakToken<Role>>,
+    State(state): State<Arc<AppState>>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    let user_id = current_user_id(&token)?;
+
+    // Keycloak's session endpoint isn't scoped to a user, so check ownership first
+    let owned = state
+        .keycloak
+        .list_user_sessions(user_id)
+        .await?
+        .iter()
+        .any(|s| s.id == session_id);
+    if !owned {
+        warn!(session_id = %session_id, "Session not found for user");
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"status": "fail", "error": "Session not found"})),
+        )
+            .into());
+    }
+
+    state.keycloak.delete_session(&session_id).await?;
+
+    info!(session_id = %session_id, "Session revoked");
+    Ok(message("Session revoked successfully"))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/auth/validate",
+    tag = "auth",
+    description = "Checks the bearer token and echoes its expiry and subject, a cheaper way for a frontend to tell whether a stored token is still good than probing a real endpoint. No new token is issued.",
+    responses(
+        (status = 200, description = "The token is valid", body = Success<TokenValidity>),
+        (status = 401, description = "Missing, invalid or expired token")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn validate_token(Extension(token): Extension<KeycloakToken<Role>>) -> Json<Success<TokenValidity>> {
+    let expires_at = DateTime::from_timestamp(token.expires_at.unix_timestamp(), 0).unwrap_or_default();
+    debug!(expires_at = %expires_at, "Token validated");
+
+    success(TokenValidity { valid: true, expires_at, subject: token.subject })
+}
@@ -0,0 +1,93 @@
+//! Auth bypass for tests, enabled with the `test-auth` cargo feature.
+//!
+//! Replaces the Keycloak layer with [`inject_test_token`], which builds a
+//! decoded token from request headers:
+//!
+//! * `x-test-subject`: the user id (UUID) the request acts as (required, 401 if missing)
+//! * `x-test-roles`: comma-separated roles, e.g. `user,admin` (defaults to `user`)
+//! * `x-test-tenant`: value of the `TENANT_CLAIM` claim (defaults to no claim, i.e. the default tenant)
+//! * `x-test-groups`: comma-separated `groups` claim, e.g. `/admins` (defaults to no claim)
+//!
+//! It also mounts [`PANIC_PATH`], a route that always panics, to exercise panic capture.
+//!
+//! The feature refuses to compile in release builds.
+
+#[cfg(not(debug_assertions))]
+compile_error!("the `test-auth` feature must never be enabled in release builds");
+
+use crate::{
+    handlers::{dev_auth::local_token, middleware::GROUPS_CLAIM},
+    models::{role::Role, state::AppState},
+};
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use axum_keycloak_auth::decode::{KeycloakToken, RawClaims};
+use std::sync::Arc;
+use tracing::debug;
+use uuid::Uuid;
+
+pub const SUBJECT_HEADER: &str = "x-test-subject";
+pub const ROLES_HEADER: &str = "x-test-roles";
+pub const TENANT_HEADER: &str = "x-test-tenant";
+pub const GROUPS_HEADER: &str = "x-test-groups";
+pub const PANIC_PATH: &str = "/api/test/panic";
+
+/// A decoded token as the Keycloak layer would hand it to handlers
+pub fn test_token(subject: Uuid, roles: &[Role]) -> KeycloakToken<Role> {
+    local_token(subject, roles, "test-auth")
+}
+
+pub async fn inject_test_token(
+    State(state): State<Arc<AppState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let subject = req
+        .headers()
+        .get(SUBJECT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| Uuid::parse_str(v).ok())
+        .ok_or((StatusCode::UNAUTHORIZED, "Missing or invalid x-test-subject header"))?;
+
+    let roles: Vec<Role> = req
+        .headers()
+        .get(ROLES_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').map(|r| Role::from(r.trim().to_string())).collect())
+        .unwrap_or_else(|| vec![Role::User]);
+
+    let tenant = req
+        .headers()
+        .get(TENANT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    let groups: Option<Vec<String>> = req
+        .headers()
+        .get(GROUPS_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.split(',').map(|g| g.trim().to_string()).collect());
+
+    debug!(subject = %subject, roles = ?roles, tenant = ?tenant, groups = ?groups, "Injecting test token");
+    req.extensions_mut().insert(test_token(subject, &roles));
+    let mut claims = RawClaims::new();
+    if let Some(tenant) = tenant {
+        claims.insert(state.config.tenant_claim.clone(), tenant.into());
+    }
+    if let Some(groups) = groups {
+        claims.insert(GROUPS_CLAIM.to_string(), groups.into());
+    }
+    if !claims.is_empty() {
+        req.extensions_mut().insert(claims);
+    }
+
+    Ok(next.run(req).await)
+}
+
+pub async fn deliberate_panic() -> Response {
+    panic!("deliberate panic from {}", PANIC_PATH)
+}
@@ -0,0 +1,116 @@
+use crate::keycloak::{KeycloakClient, KeycloakSession, KeycloakUser};
+use crate::models::{error::AppError, role::Role};
+use async_trait::async_trait;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::debug;
+use uuid::Uuid;
+
+// One listing: the role filter it was fetched with, when, and the users
+type CachedUserList = (Option<Role>, Instant, Vec<KeycloakUser>);
+
+#[derive(Default)]
+struct UserLists {
+    // Bumped by every invalidation, so a fetch that started before one can't store its stale result
+    generation: u64,
+    entries: Vec<CachedUserList>,
+}
+
+/// Wraps a [`KeycloakClient`] and keeps user listings for `ttl`, one entry per role
+/// filter, so polling admin dashboards don't hit Keycloak on every request. Any
+/// change made to a user through this client drops every entry.
+pub struct CachedKeycloakClient {
+    inner: Arc<dyn KeycloakClient>,
+    ttl: Duration,
+    user_lists: Mutex<UserLists>,
+}
+
+impl CachedKeycloakClient {
+    pub fn new(inner: Arc<dyn KeycloakClient>, ttl: Duration) -> Self {
+        Self { inner, ttl, user_lists: Mutex::new(UserLists::default()) }
+    }
+
+    fn invalidate(&self) {
+        let mut lists = self.user_lists.lock().unwrap();
+        lists.generation += 1;
+        lists.entries.clear();
+    }
+}
+
+#[async_trait]
+impl KeycloakClient for CachedKeycloakClient {
+    async fn ping(&self) -> Result<(), AppError> {
+        self.inner.ping().await
+    }
+
+    async fn get_admin_token(&self) -> Result<String, AppError> {
+        self.inner.get_admin_token().await
+    }
+
+    async fn list_users(&self, role: Option<&Role>) -> Result<Vec<KeycloakUser>, AppError> {
+        let generation = {
+            let mut lists = self.user_lists.lock().unwrap();
+            lists.entries.retain(|(_, fetched_at, _)| fetched_at.elapsed() < self.ttl);
+            if let Some((_, _, users)) = lists.entries.iter().find(|(key, _, _)| key.as_ref() == role) {
+                debug!(role = ?role, user_count = users.len(), "Serving user list from cache");
+                return Ok(users.clone());
+            }
+            lists.generation
+        };
+
+        // Errors aren't cached, so the next request tries Keycloak again
+        let users = self.inner.list_users(role).await?;
+        let mut lists = self.user_lists.lock().unwrap();
+        if lists.generation != generation {
+            debug!(role = ?role, "User changed while listing, not caching the result");
+            return Ok(users);
+        }
+        lists.entries.retain(|(key, _, _)| key.as_ref() != role);
+        lists.entries.push((role.cloned(), Instant::now(), users.clone()));
+        Ok(users)
+    }
+
+    async fn get_user(&self, id: Uuid) -> Result<KeycloakUser, AppError> {
+        self.inner.get_user(id).await
+    }
+
+    async fn count_users(&self, email_verified: Option<bool>) -> Result<i64, AppError> {
+        self.inner.count_users(email_verified).await
+    }
+
+    async fn delete_user(&self, id: Uuid) -> Result<(), AppError> {
+        let result = self.inner.delete_user(id).await;
+        self.invalidate();
+        result
+    }
+
+    async fn set_user_enabled(&self, id: Uuid, enabled: bool) -> Result<(), AppError> {
+        let result = self.inner.set_user_enabled(id, enabled).await;
+        self.invalidate();
+        result
+    }
+
+    async fn update_user(&self, id: Uuid, representation: &serde_json::Value) -> Result<(), AppError> {
+        let result = self.inner.update_user(id, representation).await;
+        self.invalidate();
+        result
+    }
+
+    async fn send_verify_email(&self, id: Uuid) -> Result<(), AppError> {
+        self.inner.send_verify_email(id).await
+    }
+
+    async fn execute_actions_email(&self, id: Uuid, actions: &[&str]) -> Result<(), AppError> {
+        self.inner.execute_actions_email(id, actions).await
+    }
+
+    async fn list_user_sessions(&self, id: Uuid) -> Result<Vec<KeycloakSession>, AppError> {
+        self.inner.list_user_sessions(id).await
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.inner.delete_session(session_id).await
+    }
+}
@@ -0,0 +1,144 @@
+use axum::http::{header, HeaderMap};
+use serde::Deserialize;
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// The resolved address of the client, inserted as a request extension by `resolve_client_ip`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+impl fmt::Display for ClientIp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// An address block such as `10.0.0.0/8`; a bare address is a single-host block
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("'{}' is not an IP address or CIDR block", s))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|prefix| *prefix <= max)
+                .ok_or_else(|| format!("'{}' has an invalid prefix length", s))?,
+            None => max,
+        };
+        Ok(Cidr { addr, prefix })
+    }
+}
+
+/// Peers whose `Forwarded` / `X-Forwarded-For` headers are believed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct TrustedProxies(pub Vec<Cidr>);
+
+impl TrustedProxies {
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|cidr| cidr.contains(ip))
+    }
+
+    /// The client address for a request from `peer`. Forwarding headers are only
+    /// read when `peer` is trusted, and then walked right to left past further
+    /// trusted hops, so a client can't spoof its address by sending the headers itself.
+    pub fn resolve(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+
+        let chain = forwarded_for(headers).unwrap_or_else(|| x_forwarded_for(headers));
+        let mut client = peer;
+        for hop in chain.iter().rev() {
+            match hop {
+                Some(ip) => {
+                    client = *ip;
+                    if !self.is_trusted(*ip) {
+                        break;
+                    }
+                }
+                // An unparsable hop can't be trusted; stop at the last known address
+                None => break,
+            }
+        }
+        client
+    }
+}
+
+// `for=` values of the standard `Forwarded` header, or `None` if it isn't sent
+fn forwarded_for(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let values: Vec<&str> = headers
+        .get_all(header::FORWARDED)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(
+        values
+            .iter()
+            .flat_map(|v| v.split(','))
+            .filter_map(|element| {
+                element.split(';').find_map(|pair| {
+                    let (key, value) = pair.split_once('=')?;
+                    key.trim().eq_ignore_ascii_case("for").then(|| parse_node(value))
+                })
+            })
+            .collect(),
+    )
+}
+
+fn x_forwarded_for(headers: &HeaderMap) -> Vec<Option<IpAddr>> {
+    headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(parse_node)
+        .collect()
+}
+
+// Accepts `1.2.3.4`, `1.2.3.4:80`, `2001:db8::1` and `"[2001:db8::1]:4711"`
+fn parse_node(value: &str) -> Option<IpAddr> {
+    let value = value.trim().trim_matches('"');
+    if let Some(rest) = value.strip_prefix('[') {
+        return rest.split(']').next()?.parse().ok();
+    }
+    value.parse().ok().or_else(|| {
+        let (host, _port) = value.rsplit_once(':')?;
+        host.parse().ok()
+    })
+}
@@ -0,0 +1,143 @@
+use chrono::{SecondsFormat, Utc};
+use std::fmt;
+use tracing::{
+    field::{Field, Visit},
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    field::RecordFields,
+    fmt::{format::Writer, FmtContext, FormatEvent, FormatFields, FormattedFields},
+    registry::LookupSpan,
+};
+
+/// `LOG_FORMAT=logfmt`: one `key=value` line per event. The keys match the JSON format's
+/// (`timestamp`, `level`, `target`, `message`, the event's fields), with the innermost
+/// span's name as `span` and the fields of every enclosing span flattened in after it.
+pub struct Logfmt;
+
+/// Renders event and span fields as logfmt pairs; pair with [`Logfmt`]
+pub struct LogfmtFields;
+
+impl<S, N> FormatEvent<S, N> for Logfmt
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        let metadata = event.metadata();
+        write!(
+            writer,
+            "timestamp={} level={} target=",
+            Utc::now().to_rfc3339_opts(SecondsFormat::Micros, true),
+            metadata.level()
+        )?;
+        write_value(&mut writer, metadata.target())?;
+
+        writer.write_char(' ')?;
+        ctx.format_fields(writer.by_ref(), event)?;
+
+        if let Some(scope) = ctx.event_scope() {
+            let spans: Vec<_> = scope.from_root().collect();
+            if let Some(leaf) = spans.last() {
+                write!(writer, " span=")?;
+                write_value(&mut writer, leaf.name())?;
+            }
+            for span in &spans {
+                let extensions = span.extensions();
+                if let Some(fields) = extensions.get::<FormattedFields<N>>() {
+                    if !fields.is_empty() {
+                        write!(writer, " {}", fields)?;
+                    }
+                }
+            }
+        }
+
+        writeln!(writer)
+    }
+}
+
+impl<'writer> FormatFields<'writer> for LogfmtFields {
+    fn format_fields<R: RecordFields>(&self, writer: Writer<'writer>, fields: R) -> fmt::Result {
+        let mut visitor = PairVisitor { writer, first: true, result: Ok(()) };
+        fields.record(&mut visitor);
+        visitor.result
+    }
+}
+
+struct PairVisitor<'writer> {
+    writer: Writer<'writer>,
+    first: bool,
+    result: fmt::Result,
+}
+
+impl PairVisitor<'_> {
+    fn pair(&mut self, field: &Field, value: &dyn fmt::Display, quote: bool) {
+        if self.result.is_err() {
+            return;
+        }
+        self.result = (|| {
+            if !self.first {
+                self.writer.write_char(' ')?;
+            }
+            self.first = false;
+            write!(self.writer, "{}=", field.name())?;
+            if quote {
+                write_value(&mut self.writer, &value.to_string())
+            } else {
+                write!(self.writer, "{}", value)
+            }
+        })();
+    }
+}
+
+impl Visit for PairVisitor<'_> {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.pair(field, &value, false);
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.pair(field, &value, false);
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.pair(field, &value, false);
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.pair(field, &value, false);
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.pair(field, &value, true);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.pair(field, &format_args!("{:?}", value), true);
+    }
+}
+
+// Bare when it can't be misread, otherwise quoted with `"`, `\` and line breaks escaped,
+// so an event always stays on one line
+fn write_value(writer: &mut Writer<'_>, value: &str) -> fmt::Result {
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c == ' ' || c == '=' || c == '"' || c == '\\' || c.is_control());
+    if !needs_quotes {
+        return writer.write_str(value);
+    }
+
+    writer.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => writer.write_str("\\\"")?,
+            '\\' => writer.write_str("\\\\")?,
+            '\n' => writer.write_str("\\n")?,
+            '\r' => writer.write_str("\\r")?,
+            '\t' => writer.write_str("\\t")?,
+            c if c.is_control() => write!(writer, "\\u{{{:x}}}", c as u32)?,
+            c => writer.write_char(c)?,
+        }
+    }
+    writer.write_char('"')
+}
@@ -0,0 +1,180 @@
+//! The task SQL the handlers run. Every statement is a fixed string and user input only ever
+//! arrives as a bind parameter, so each shape is prepared once per connection and reused.
+
+use std::{fmt, str::FromStr};
+
+/// `$1` = task id, `$2` = user id, `$3` = tenant id
+pub const OWNED_TASK: &str = "SELECT * FROM tasks WHERE id = $1 AND user_id = $2 AND tenant_id = $3";
+
+/// `$1` = task id, `$2` = user id, `$3` = tenant id; no row back means the caller owns no such task
+pub const DELETE_OWNED_TASK: &str = "DELETE FROM tasks WHERE id = $1 AND user_id = $2 AND tenant_id = $3 RETURNING id";
+
+/// `$1` = name, `$2` = description, `$3` = user id, `$4` = tenant id, `$5` = optional client id.
+/// Appends the task after the owner's last position. Shared by create and import.
+pub const INSERT_TASK: &str = concat!(
+    "INSERT INTO tasks (name, description, user_id, tenant_id, position, client_id, created_at, updated_at)",
+    " VALUES ($1, $2, $3, $4,",
+    " COALESCE((SELECT MAX(position) FROM tasks WHERE user_id = $3 AND tenant_id = $4), 0) + 1,",
+    " $5, NOW(), NOW())",
+    " RETURNING *"
+);
+
+/// `$1` = user id, `$2` = tenant id. Serializes the user's task inserts until the transaction ends,
+/// so the MAX_TASKS_PER_USER count, the duplicate check and the new position can't be raced by a
+/// concurrent insert
+pub const LOCK_USER_TASKS: &str = "SELECT pg_advisory_xact_lock(hashtextextended($1::text || '/' || $2, 0))";
+
+/// `$1` = user id, `$2` = tenant id, `$3` = name, `$4` = window in minutes
+pub const RECENT_DUPLICATE_EXISTS: &str = concat!(
+    "SELECT EXISTS (SELECT 1 FROM tasks WHERE user_id = $1 AND tenant_id = $2 AND name = $3",
+    " AND created_at > NOW() - make_interval(mins => $4))"
+);
+
+/// `$1` = task id, `$2` = tenant id; any owner, returning the owner's id
+pub const ADMIN_DELETE_TASK: &str = "DELETE FROM tasks WHERE id = $1 AND tenant_id = $2 RETURNING user_id";
+
+/// `$1` = user id; every tenant, for a user removed from the realm
+pub const DELETE_USER_TASKS: &str = "DELETE FROM tasks WHERE user_id = $1";
+
+/// `$1` = setting key
+pub const SETTING: &str = "SELECT value FROM settings WHERE key = $1";
+
+/// `$1` = setting key, `$2` = value
+pub const UPSERT_SETTING: &str = concat!(
+    "INSERT INTO settings (key, value, updated_at) VALUES ($1, $2, NOW()) ",
+    "ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()"
+);
+
+/// `$1` = user id, `$2` = tenant id, `$3` = task ids; locks the rows a reorder rewrites
+pub const LOCK_OWNED_TASKS: &str =
+    "SELECT id FROM tasks WHERE user_id = $1 AND tenant_id = $2 AND id = ANY($3) FOR UPDATE";
+
+/// `$1` = user id, `$2` = tenant id, `$3` = task ids. The listed tasks take positions 1..n
+/// and the rest follow in their previous order.
+pub const REORDER_TASKS: &str = concat!(
+    "UPDATE tasks t SET position = ordered.new_position FROM (",
+    "SELECT id, ROW_NUMBER() OVER (ORDER BY array_position($3, id) NULLS LAST, position, created_at)::int AS new_position",
+    " FROM tasks WHERE user_id = $1 AND tenant_id = $2",
+    ") ordered WHERE t.id = ordered.id"
+);
+
+/// `$1` = user id, `$2` = tenant id
+pub const USER_TASK_COUNT: &str = "SELECT COUNT(*) FROM tasks WHERE user_id = $1 AND tenant_id = $2";
+
+/// `$1` = user id, `$2` = tenant id, `$3` = limit
+pub const RECENT_TASKS: &str =
+    "SELECT * FROM tasks WHERE user_id = $1 AND tenant_id = $2 ORDER BY updated_at DESC LIMIT $3";
+
+/// `$1` = user id, `$2` = tenant id; oldest first, so exports are stable between runs
+pub const EXPORT_TASKS: &str = "SELECT * FROM tasks WHERE user_id = $1 AND tenant_id = $2 ORDER BY created_at";
+
+// A NULL user filter matches every owner
+macro_rules! admin_where {
+    () => {
+        " WHERE tenant_id = $1 AND ($2::uuid IS NULL OR user_id = $2)"
+    };
+}
+
+/// `$1` = tenant id, `$2` = optional user id
+pub const ADMIN_TASK_COUNT: &str = concat!("SELECT COUNT(*) FROM tasks", admin_where!());
+
+/// `$1` = tenant id, `$2` = optional user id, `$3` = limit, `$4` = offset
+pub const ADMIN_TASK_PAGE: &str =
+    concat!("SELECT * FROM tasks", admin_where!(), " ORDER BY created_at DESC, id LIMIT $3 OFFSET $4");
+
+macro_rules! list_tasks {
+    ($order:literal) => {
+        concat!("SELECT * FROM tasks WHERE user_id = $1 AND tenant_id = $2", $order)
+    };
+}
+
+/// `$1` = user id, `$2` = tenant id; the order reorder_tasks writes
+pub const TASKS_BY_POSITION: &str = list_tasks!(" ORDER BY position, created_at");
+
+/// `GET /api/tasks` for `?sort=`, binding `$1` = user id and `$2` = tenant id.
+/// `None` for a sort the endpoint doesn't support.
+pub fn list_tasks(sort: Option<&str>) -> Option<&'static str> {
+    match sort {
+        None => Some(list_tasks!("")),
+        Some("position") => Some(TASKS_BY_POSITION),
+        Some("created_at") => Some(list_tasks!(" ORDER BY created_at")),
+        Some(_) => None,
+    }
+}
+
+// Unset criteria are bound as NULL and switch their condition off, so every search shares one WHERE
+macro_rules! search_where {
+    () => {
+        concat!(
+            " WHERE user_id = $1 AND tenant_id = $2",
+            " AND ($3::text IS NULL OR name ILIKE $3 OR description ILIKE $3)",
+            " AND ($4::timestamptz IS NULL OR created_at >= $4)",
+            " AND ($5::timestamptz IS NULL OR created_at <= $5)"
+        )
+    };
+}
+
+macro_rules! search_page {
+    ($order:literal) => {
+        concat!("SELECT * FROM tasks", search_where!(), " ORDER BY ", $order, " LIMIT $6 OFFSET $7")
+    };
+}
+
+/// `$1` = user id, `$2` = tenant id, `$3` = optional ILIKE pattern, `$4`/`$5` = optional created_at bounds
+pub const SEARCH_TASK_COUNT: &str = concat!("SELECT COUNT(*) FROM tasks", search_where!());
+
+/// Orderings accepted by `POST /api/tasks/search`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TaskSort {
+    /// Newest first
+    #[default]
+    CreatedAt,
+    /// Most recently updated first
+    UpdatedAt,
+    Position,
+    Name,
+}
+
+impl TaskSort {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskSort::CreatedAt => "created_at",
+            TaskSort::UpdatedAt => "updated_at",
+            TaskSort::Position => "position",
+            TaskSort::Name => "name",
+        }
+    }
+
+    /// The page statement: the `SEARCH_TASK_COUNT` parameters, then `$6` = limit and `$7` = offset
+    pub fn search_sql(self) -> &'static str {
+        match self {
+            TaskSort::CreatedAt => search_page!("created_at DESC, id"),
+            TaskSort::UpdatedAt => search_page!("updated_at DESC, id"),
+            TaskSort::Position => search_page!("position, created_at"),
+            TaskSort::Name => search_page!("name, created_at"),
+        }
+    }
+}
+
+impl FromStr for TaskSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "created_at" => Ok(TaskSort::CreatedAt),
+            "updated_at" => Ok(TaskSort::UpdatedAt),
+            "position" => Ok(TaskSort::Position),
+            "name" => Ok(TaskSort::Name),
+            other => Err(format!(
+                "Unsupported sort '{}', expected created_at, updated_at, position or name",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for TaskSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
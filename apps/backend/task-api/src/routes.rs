@@ -1,48 +1,257 @@
 use crate::{
     handlers::{
-        health::health,
-        logging_middleware::logging_middleware,
-        middleware::admin_guard,
-        task::{create_task, delete_task, list_tasks},
-        user::{delete_user, list_users},
+        admin::{admin_delete_task, admin_list_tasks, admin_stats, admin_user_tasks, maintenance_status, set_maintenance},
+        dev_auth::inject_dev_user,
+        export::export_tasks,
+        import::import_tasks,
+        session::{list_sessions, revoke_session, validate_token},
+        fallback::{method_not_allowed, not_found},
+        health::{health, ready},
+        logging_middleware::{logging_middleware, user_span},
+        middleware::{
+            admin_guard, catch_panic, docs_basic_auth, group_guard, maintenance_gate, normalize_bearer, preflight_no_content, require_json, resolve_client_ip,
+            shed_response, timeout_response, unsupported_encoding_response,
+        },
+        task::{create_task, delete_task, list_tasks, recent_tasks, reorder_tasks, search_tasks},
+        transaction::transaction,
+        user::{
+            delete_user, disable_user, enable_user, get_user, list_users, send_reset_password_email,
+            send_verify_email, update_user,
+        },
     },
-    models::{role::Role, state::AppState},
+    models::{config::Config, state::AppState},
+    postman,
 };
 use axum::{
+    error_handling::HandleErrorLayer,
+    extract::DefaultBodyLimit,
+    http::{header, HeaderValue, Method, StatusCode},
     middleware,
     routing::{delete, get, post},
-    Router,
+    Json, Router,
 };
 use axum_keycloak_auth::instance::KeycloakAuthInstance;
-use axum_keycloak_auth::{layer::KeycloakAuthLayer, PassthroughMode};
-use std::sync::Arc;
+#[cfg(not(feature = "test-auth"))]
+use {
+    crate::models::role::Role,
+    axum_keycloak_auth::{layer::KeycloakAuthLayer, PassthroughMode},
+};
+use std::{sync::Arc, time::Duration};
+use tower::{limit::GlobalConcurrencyLimitLayer, load_shed::LoadShedLayer, ServiceBuilder};
+use utoipa::openapi::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+use tower_http::{
+    cors::{AllowOrigin, CorsLayer},
+    decompression::RequestDecompressionLayer,
+    timeout::TimeoutLayer,
+};
+
+fn cors_layer(config: &Config) -> Option<CorsLayer> {
+    if config.cors_allowed_origins.is_empty() {
+        return None;
+    }
+
+    let origins = if config.cors_allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .cors_allowed_origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([
+                Method::GET,
+                Method::HEAD,
+                Method::POST,
+                Method::PUT,
+                Method::PATCH,
+                Method::DELETE,
+            ])
+            .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT])
+            .max_age(Duration::from_secs(3600)),
+    )
+}
+
+/// Where the Postman collection generated from the OpenAPI document is served, under `BASE_PATH`
+pub const POSTMAN_PATH: &str = "/api-docs/postman.json";
+
+/// Swagger UI, the OpenAPI document and its Postman collection, behind basic
+/// auth when configured; empty when `SWAGGER_ENABLED` is off so all of them 404
+pub fn docs_routes(config: &Config, openapi: OpenApi) -> Router {
+    if !config.swagger_enabled {
+        return Router::new();
+    }
+
+    let collection = Json(postman::collection(&openapi));
+    let docs: Router = SwaggerUi::new(format!("{}{}", config.base_path, config.swagger_path))
+        .url(format!("{}{}", config.base_path, config.openapi_path), openapi)
+        .into();
+    let docs = docs.route(
+        &format!("{}{}", config.base_path, POSTMAN_PATH),
+        get(move || async move { collection }),
+    );
+    match &config.docs_basic_auth {
+        Some(credentials) => docs.layer(middleware::from_fn_with_state(
+            Arc::new(credentials.clone()),
+            docs_basic_auth,
+        )),
+        None => docs,
+    }
+}
+
+/// Caps `router` at `limit` requests in flight. Requests past it are answered at once with
+/// 503 and `Retry-After` instead of waiting for a slot.
+pub fn shed_load<S: Clone + Send + Sync + 'static>(router: Router<S>, limit: usize) -> Router<S> {
+    // `Router::layer` wraps every route separately; the global layer shares one semaphore between them
+    router.layer(
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(shed_response))
+            .layer(LoadShedLayer::new())
+            .layer(GlobalConcurrencyLimitLayer::new(limit)),
+    )
+}
 
 pub fn create_routes(state: Arc<AppState>, keycloak_instance: Arc<KeycloakAuthInstance>) -> Router {
-    let auth_layer: KeycloakAuthLayer<Role> = KeycloakAuthLayer::<Role>::builder()
-        .instance(keycloak_instance)
-        .passthrough_mode(PassthroughMode::Block)
-        .persist_raw_claims(true)
-        .expected_audiences(vec![state.config.audience.clone()])
-        .required_roles(vec![Role::User])
-        .build();
+    #[cfg(not(feature = "test-auth"))]
+    let keycloak_layer = |mode: PassthroughMode| -> KeycloakAuthLayer<Role> {
+        KeycloakAuthLayer::<Role>::builder()
+            .instance(keycloak_instance.clone())
+            .passthrough_mode(mode)
+            .persist_raw_claims(true)
+            .expected_audiences(state.config.audiences.clone())
+            .required_roles(state.config.required_roles.clone())
+            .build()
+    };
+    #[cfg(not(feature = "test-auth"))]
+    let auth_layer = keycloak_layer(PassthroughMode::Block);
+    // DEV_MODE lets requests without a valid token through to inject_dev_user; admin routes keep blocking
+    #[cfg(not(feature = "test-auth"))]
+    let user_auth_layer = if state.config.dev_mode {
+        keycloak_layer(PassthroughMode::Pass)
+    } else {
+        auth_layer.clone()
+    };
+
+    // Tests authenticate through headers instead of real Keycloak tokens
+    #[cfg(feature = "test-auth")]
+    let auth_layer = {
+        let _ = keycloak_instance;
+        middleware::from_fn_with_state(state.clone(), crate::handlers::test_auth::inject_test_token)
+    };
+    #[cfg(feature = "test-auth")]
+    let user_auth_layer = auth_layer.clone();
 
-    let public_routes = Router::new().route("/api/health", get(health));
+    // Every GET route also answers HEAD with the same status and headers and no body
+    let public_routes = Router::new()
+        .route("/api/health", get(health))
+        .route("/api/health/ready", get(ready));
+    #[cfg(feature = "test-auth")]
+    let public_routes = public_routes.route(
+        crate::handlers::test_auth::PANIC_PATH,
+        get(crate::handlers::test_auth::deliberate_panic),
+    );
 
     let protected_routes = Router::new()
-        .route("/api/tasks", post(create_task).get(list_tasks))
+        .route(
+            "/api/tasks",
+            post(create_task)
+                .layer(middleware::from_fn_with_state(state.clone(), transaction))
+                .get(list_tasks),
+        )
+        .route("/api/tasks/recent", get(recent_tasks))
+        .route("/api/tasks/search", post(search_tasks))
+        .route(
+            "/api/tasks/reorder",
+            post(reorder_tasks).layer(middleware::from_fn_with_state(state.clone(), transaction)),
+        )
+        .route("/api/tasks/export", get(export_tasks))
         .route("/api/tasks/{id}", delete(delete_task))
-        .layer(auth_layer.clone());
+        .route("/api/auth/validate", get(validate_token))
+        .route("/api/auth/sessions", get(list_sessions))
+        .route("/api/auth/sessions/{session_id}", delete(revoke_session))
+        .layer(middleware::from_fn(user_span))
+        .layer(middleware::from_fn_with_state(state.clone(), inject_dev_user))
+        .layer(user_auth_layer.clone())
+        .layer(middleware::from_fn_with_state(state.clone(), maintenance_gate));
 
     let admin_routes = Router::new()
+        .route("/api/admin/stats", get(admin_stats))
+        .route("/api/admin/tasks", get(admin_list_tasks))
+        .route("/api/admin/tasks/{id}", delete(admin_delete_task))
         .route("/api/admin/users", get(list_users))
-        .route("/api/admin/users/{id}", delete(delete_user))
+        .route("/api/admin/users/{id}", get(get_user).patch(update_user).delete(delete_user))
+        .route("/api/admin/users/{id}/tasks", get(admin_user_tasks))
+        .route("/api/admin/users/{id}/disable", post(disable_user))
+        .route("/api/admin/users/{id}/enable", post(enable_user))
+        .route("/api/admin/users/{id}/send-verify-email", post(send_verify_email))
+        .route("/api/admin/users/{id}/reset-password-email", post(send_reset_password_email))
+        .route("/api/admin/maintenance", get(maintenance_status).put(set_maintenance));
+    let admin_routes = match state.config.admin_groups.as_slice() {
+        [] => admin_routes,
+        groups => admin_routes.layer(middleware::from_fn_with_state(Arc::<[String]>::from(groups), group_guard)),
+    };
+    let admin_routes = admin_routes
         .layer(middleware::from_fn(admin_guard))
+        .layer(middleware::from_fn(user_span))
         .layer(auth_layer);
 
-    Router::new()
-        .merge(public_routes)
+    // Uploads are multipart, so they sit outside the JSON content-type check
+    let upload_routes = Router::new()
+        .route(
+            "/api/tasks/import",
+            post(import_tasks).layer(middleware::from_fn_with_state(state.clone(), transaction)),
+        )
+        .layer(DefaultBodyLimit::max(state.config.import_max_bytes))
+        .layer(middleware::from_fn(user_span))
+        .layer(middleware::from_fn_with_state(state.clone(), inject_dev_user))
+        .layer(user_auth_layer)
+        .layer(middleware::from_fn_with_state(state.clone(), maintenance_gate));
+
+    // Health checks stay outside the timeout so probes are never cut short
+    let request_timeout = Duration::from_secs(state.config.request_timeout_secs);
+    let json_routes = Router::new()
         .merge(protected_routes)
         .merge(admin_routes)
+        .layer(middleware::from_fn(require_json));
+    let api_routes = Router::new()
+        .merge(json_routes)
+        .merge(upload_routes)
+        // Runs before the auth layers so they only ever see a well-formed bearer header
+        .layer(middleware::from_fn(normalize_bearer))
+        // Gzipped bodies are inflated before any extractor (and its body limit) sees them
+        .layer(RequestDecompressionLayer::new())
+        .layer(middleware::map_response(unsupported_encoding_response))
+        .layer(TimeoutLayer::with_status_code(StatusCode::GATEWAY_TIMEOUT, request_timeout))
+        .layer(middleware::map_response(timeout_response));
+    // Health checks stay outside the limit too, so a busy pod isn't restarted for being busy
+    let api_routes = match state.config.max_concurrent_requests {
+        Some(limit) => shed_load(api_routes, limit),
+        None => api_routes,
+    };
+
+    let router = Router::new()
+        .merge(public_routes)
+        .merge(api_routes)
+        .fallback(not_found)
+        .method_not_allowed_fallback(method_not_allowed);
+
+    // CORS wraps every route, outside the auth layers, so preflights never need a token
+    let router = match cors_layer(&state.config) {
+        Some(cors) => router
+            .layer(cors)
+            .layer(middleware::from_fn(preflight_no_content)),
+        None => router,
+    };
+
+    router
+        .layer(middleware::from_fn(catch_panic))
         .layer(middleware::from_fn(logging_middleware))
+        .layer(middleware::from_fn_with_state(state.clone(), resolve_client_ip))
         .with_state(state)
 }
@@ -0,0 +1,80 @@
+use serde_json::json;
+use task_api::postman::collection;
+use utoipa::openapi::OpenApi;
+
+fn openapi() -> OpenApi {
+    serde_json::from_value(json!({
+        "openapi": "3.1.0",
+        "info": { "title": "task-api", "version": "0.1.0" },
+        "servers": [{ "url": "/v1" }],
+        "tags": [{ "name": "tasks" }, { "name": "health" }],
+        "paths": {
+            "/api/tasks": {
+                "post": {
+                    "tags": ["tasks"],
+                    "operationId": "create_task",
+                    "requestBody": {
+                        "content": { "application/json": { "schema": { "$ref": "#/components/schemas/CreateTaskSchema" } } },
+                        "required": true
+                    },
+                    "responses": {}
+                }
+            },
+            "/api/tasks/{id}": {
+                "delete": {
+                    "tags": ["tasks"],
+                    "operationId": "delete_task",
+                    "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "string" } }],
+                    "responses": {}
+                }
+            },
+            "/health": {
+                "get": { "tags": ["health"], "operationId": "health", "security": [], "responses": {} }
+            }
+        },
+        "components": {
+            "schemas": {
+                "CreateTaskSchema": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" }, "description": { "type": ["string", "null"] } }
+                }
+            }
+        }
+    }))
+    .unwrap()
+}
+
+#[test]
+fn collection_has_a_folder_per_tag_and_bearer_auth() {
+    let collection = collection(&openapi());
+
+    assert_eq!(
+        collection["info"]["schema"],
+        "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"
+    );
+    assert_eq!(collection["auth"]["bearer"][0]["value"], "{{bearerToken}}");
+    assert_eq!(collection["variable"][0], json!({ "key": "baseUrl", "value": "/v1" }));
+
+    let folders = collection["item"].as_array().unwrap();
+    assert_eq!(folders.len(), 2);
+    assert_eq!(folders[0]["name"], "tasks");
+    assert_eq!(folders[0]["item"].as_array().unwrap().len(), 2);
+    assert_eq!(folders[1]["item"][0]["request"]["auth"]["type"], "noauth");
+}
+
+#[test]
+fn requests_use_path_variables_and_body_skeletons() {
+    let collection = collection(&openapi());
+    let tasks = &collection["item"][0]["item"];
+
+    let create = &tasks[0]["request"];
+    assert_eq!(create["method"], "POST");
+    let body: serde_json::Value = serde_json::from_str(create["body"]["raw"].as_str().unwrap()).unwrap();
+    assert_eq!(body, json!({ "name": "", "description": "" }));
+
+    let delete = &tasks[1]["request"];
+    assert_eq!(delete["method"], "DELETE");
+    assert_eq!(delete["url"]["raw"], "{{baseUrl}}/api/tasks/:id");
+    assert_eq!(delete["url"]["variable"][0]["key"], "id");
+    assert!(delete.get("auth").is_none());
+}
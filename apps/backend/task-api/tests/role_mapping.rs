@@ -0,0 +1,18 @@
+//! Own test binary: the admin role name is process-wide and set once.
+
+use task_api::models::role::{admin_role_name, set_admin_role_name, Role};
+
+#[test]
+fn custom_admin_role_name_maps_to_admin() {
+    set_admin_role_name("task-admin");
+
+    assert_eq!(admin_role_name(), "task-admin");
+    assert_eq!(Role::from("task-admin".to_string()), Role::Admin);
+    assert_eq!(Role::from("Task-Admin".to_string()), Role::Admin);
+    // The default name no longer grants admin once a custom one is configured
+    assert_eq!(Role::from("admin".to_string()), Role::User);
+    assert_eq!(Role::Admin.realm_name(), "task-admin");
+    // The app-level name used in query params and responses is unchanged
+    assert_eq!("admin".parse::<Role>(), Ok(Role::Admin));
+    assert_eq!(Role::Admin.to_string(), "admin");
+}
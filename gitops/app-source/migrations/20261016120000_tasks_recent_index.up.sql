@@ -0,0 +1,2 @@
+-- Serves "most recently updated tasks for a user" without sorting the user's whole task list
+CREATE INDEX IF NOT EXISTS "idx_tasks_user_id_updated_at" ON "tasks"("user_id", "updated_at" DESC);
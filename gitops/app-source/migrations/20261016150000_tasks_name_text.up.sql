@@ -0,0 +1,2 @@
+-- The name length limit is enforced by the API (TASK_NAME_MAX_CHARS) so it can be configured
+ALTER TABLE "tasks" ALTER COLUMN "name" TYPE TEXT;
@@ -0,0 +1,32 @@
+use crate::models::error::AppError;
+use axum::{
+    extract::{FromRequestParts, Path},
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use serde_json::json;
+use tracing::warn;
+use uuid::Uuid;
+
+/// A `{id}` path segment parsed as a UUID. Unlike `Path<Uuid>`, a malformed id is
+/// answered with our `400 {"status":"fail","error":"invalid id"}` envelope.
+#[derive(Debug, Clone, Copy)]
+pub struct IdPath(pub Uuid);
+
+impl<S: Send + Sync> FromRequestParts<S> for IdPath {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match Path::<Uuid>::from_request_parts(parts, state).await {
+            Ok(Path(id)) => Ok(IdPath(id)),
+            Err(rejection) => {
+                warn!(path = %parts.uri.path(), error = %rejection.body_text(), "Rejected malformed id");
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"status": "fail", "error": "invalid id"})),
+                )
+                    .into())
+            }
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use axum::{http::StatusCode, Json};
+use serde_json::json;
+
+// Keep unknown routes and methods in the same JSON envelope as every other error
+pub async fn not_found() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "status": "fail",
+            "error": "Not Found"
+        })),
+    )
+}
+
+pub async fn method_not_allowed() -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(json!({
+            "status": "fail",
+            "error": "Method Not Allowed"
+        })),
+    )
+}
@@ -1,18 +1,114 @@
-use axum::Json;
+use crate::models::state::AppState;
+use axum::{extract::State, http::StatusCode, Json};
 use serde_json::json;
-use tracing::debug;
+use std::{future::Future, sync::Arc, time::Instant};
+use tracing::{debug, warn};
 
 #[utoipa::path(
     get,
     path = "/api/health",
+    tag = "health",
     responses(
-        (status = 200, description = "App up and running", body = serde_json::Value)
+        (status = 200, description = "App up and running, plus any `HEALTH_FIELDS`", body = serde_json::Value,
+            example = json!({"status": "Active", "service": "task-api", "env": "prod"}))
     )
 )]
-pub async fn health() -> Json<serde_json::Value> {
+pub async fn health(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
     debug!("Health check requested");
-    
-    Json(json!({
+
+    let mut body = json!({
         "status": "Active"
-    }))
+    });
+    for (key, value) in &state.config.health_fields {
+        body[key] = json!(value);
+    }
+    Json(body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/health/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies are up", body = serde_json::Value),
+        (status = 503, description = "At least one dependency is down", body = serde_json::Value)
+    )
+)]
+pub async fn ready(State(state): State<Arc<AppState>>) -> (StatusCode, Json<serde_json::Value>) {
+    debug!("Readiness check requested");
+
+    let report = check_dependencies(&state).await;
+    let status = if report.all_up() {
+        StatusCode::OK
+    } else {
+        warn!(
+            db_up = report.db.up,
+            db_read_up = report.db_read.as_ref().map(|s| s.up),
+            keycloak_up = report.keycloak.up,
+            "Readiness check failed"
+        );
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report.to_json()))
+}
+
+pub struct DependencyStatus {
+    pub up: bool,
+    pub latency_ms: u128,
+}
+
+/// Result of probing every external dependency, shared by readiness and `--check-config`
+pub struct DependencyReport {
+    pub db: DependencyStatus,
+    /// Only probed when a read replica is configured
+    pub db_read: Option<DependencyStatus>,
+    pub keycloak: DependencyStatus,
+}
+
+impl DependencyReport {
+    pub fn all_up(&self) -> bool {
+        self.db.up && self.db_read.as_ref().is_none_or(|s| s.up) && self.keycloak.up
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut report = json!({
+            "status": if self.all_up() { "ready" } else { "not_ready" },
+            "db": dependency(&self.db),
+            "keycloak": dependency(&self.keycloak),
+        });
+        if let Some(db_read) = &self.db_read {
+            report["db_read"] = dependency(db_read);
+        }
+        report
+    }
+}
+
+pub async fn check_dependencies(state: &AppState) -> DependencyReport {
+    let db = timed(async {
+        sqlx::query("SELECT 1").execute(&state.db).await.is_ok()
+    })
+    .await;
+    let db_read = match state.config.database_read_url {
+        Some(_) => Some(
+            timed(async { sqlx::query("SELECT 1").execute(&state.db_read).await.is_ok() }).await,
+        ),
+        None => None,
+    };
+    let keycloak = timed(async { state.keycloak.ping().await.is_ok() }).await;
+
+    DependencyReport { db, db_read, keycloak }
+}
+
+async fn timed(check: impl Future<Output = bool>) -> DependencyStatus {
+    let start = Instant::now();
+    let up = check.await;
+    DependencyStatus { up, latency_ms: start.elapsed().as_millis() }
+}
+
+fn dependency(status: &DependencyStatus) -> serde_json::Value {
+    json!({
+        "status": if status.up { "up" } else { "down" },
+        "latency_ms": status.latency_ms
+    })
 }
@@ -1,11 +1,27 @@
 use axum::{
-    extract::{Extension, Request},
-    http::StatusCode,
+    body::HttpBody,
+    extract::{ConnectInfo, Extension, Request, State},
+    http::{header, HeaderValue, Method, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
+    Json,
 };
-use axum_keycloak_auth::decode::KeycloakToken;
-use crate::models::role::Role;
+use axum_keycloak_auth::decode::{KeycloakToken, RawClaims};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures_util::FutureExt;
+use serde_json::{json, Value};
+use subtle::ConstantTimeEq;
+use crate::handlers::logging_middleware::RequestId;
+use crate::models::{
+    client_ip::ClientIp, config::BasicAuthCredentials, error::AppError, logging::panic_message, role::Role,
+    state::AppState,
+};
+use std::{
+    net::SocketAddr,
+    panic::AssertUnwindSafe,
+    sync::{atomic::Ordering, Arc},
+};
+use tracing::error;
 
 pub async fn admin_guard(
     Extension(token): Extension<KeycloakToken<Role>>,
@@ -18,3 +34,241 @@ pub async fn admin_guard(
 
     Ok(next.run(req).await)
 }
+
+/// Token claim listing the caller's Keycloak groups, filled by the group membership mapper
+pub const GROUPS_CLAIM: &str = "groups";
+
+/// Requires membership of one of the groups in its state, read from the token's `groups` claim.
+/// Membership of a subgroup counts for its parent (`/admins/eu` for `/admins`), and names match
+/// with or without the leading `/`, so the mapper's "full group path" option may be on or off.
+pub async fn group_guard(
+    State(required): State<Arc<[String]>>,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, &'static str)> {
+    let member = req
+        .extensions()
+        .get::<RawClaims>()
+        .and_then(|claims| claims.get(GROUPS_CLAIM))
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .any(|group| required.iter().any(|r| in_group(group, r)));
+    if !member {
+        return Err((StatusCode::FORBIDDEN, "Group membership required"));
+    }
+
+    Ok(next.run(req).await)
+}
+
+fn in_group(group: &str, required: &str) -> bool {
+    let group = group.trim_start_matches('/');
+    let required = required.trim_start_matches('/');
+    group == required || group.strip_prefix(required).is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Rewrites `Authorization` to the exact `Bearer <token>` form the auth layer expects,
+/// accepting any scheme casing and extra whitespace. A header that isn't a bearer
+/// token at all gets a 401 here; a missing header is left to the auth layer.
+pub async fn normalize_bearer(mut req: Request, next: Next) -> Response {
+    let Some(value) = req.headers().get(header::AUTHORIZATION) else {
+        return next.run(req).await;
+    };
+
+    let token = value
+        .to_str()
+        .ok()
+        .map(str::trim)
+        .and_then(|v| v.split_once(char::is_whitespace))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("bearer"))
+        .map(|(_, token)| token.trim())
+        .filter(|token| !token.is_empty() && !token.contains(char::is_whitespace))
+        .and_then(|token| HeaderValue::from_str(&format!("Bearer {}", token)).ok());
+
+    let Some(token) = token else {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Bearer")],
+            Json(json!({
+                "status": "fail",
+                "error": "Authorization header must be 'Bearer <token>'"
+            })),
+        )
+            .into_response();
+    };
+
+    req.headers_mut().insert(header::AUTHORIZATION, token);
+    next.run(req).await
+}
+
+/// HTTP Basic auth for the docs routes; the API itself keeps its Keycloak auth
+pub async fn docs_basic_auth(
+    State(credentials): State<Arc<BasicAuthCredentials>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let expected = format!("{}:{}", credentials.username, credentials.password);
+    let authorized = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split_once(' '))
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("basic"))
+        .and_then(|(_, encoded)| STANDARD.decode(encoded.trim()).ok())
+        .is_some_and(|decoded| bool::from(decoded.ct_eq(expected.as_bytes())));
+
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, r#"Basic realm="task-api docs", charset="UTF-8""#)],
+            Json(json!({
+                "status": "fail",
+                "error": "Authentication required"
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+// RequestDecompressionLayer answers unknown Content-Encodings with an empty 415; give it our envelope
+pub async fn unsupported_encoding_response(response: Response) -> Response {
+    if response.status() != StatusCode::UNSUPPORTED_MEDIA_TYPE || response.body().size_hint().exact() != Some(0) {
+        return response;
+    }
+
+    let accept_encoding = response.headers().get(header::ACCEPT_ENCODING).cloned();
+    let mut response = (
+        StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        Json(json!({
+            "status": "fail",
+            "error": "Unsupported Content-Encoding, expected gzip or identity"
+        })),
+    )
+        .into_response();
+    if let Some(accept_encoding) = accept_encoding {
+        response.headers_mut().insert(header::ACCEPT_ENCODING, accept_encoding);
+    }
+    response
+}
+
+// Resolves the caller's address once, honouring forwarding headers only from TRUSTED_PROXIES,
+// and stores it as a `ClientIp` extension for the logger and later middleware
+pub async fn resolve_client_ip(State(state): State<Arc<AppState>>, mut req: Request, next: Next) -> Response {
+    if let Some(ConnectInfo(peer)) = req.extensions().get::<ConnectInfo<SocketAddr>>().copied() {
+        let client_ip = state.config.trusted_proxies.resolve(peer.ip(), req.headers());
+        req.extensions_mut().insert(ClientIp(client_ip));
+    }
+
+    next.run(req).await
+}
+
+// User routes answer 503 while maintenance mode is on; admin and health routes sit outside this
+pub async fn maintenance_gate(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    if !state.maintenance.load(Ordering::Relaxed) {
+        return next.run(req).await;
+    }
+
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({
+            "status": "fail",
+            "error": "maintenance"
+        })),
+    )
+        .into_response()
+}
+
+// A panicking handler would drop the connection; answer 500 with our envelope instead and log
+// the panic against the request id. Sits just inside logging_middleware, which sets the id.
+pub async fn catch_panic(req: Request, next: Next) -> Response {
+    let request_id = req
+        .extensions()
+        .get::<RequestId>()
+        .map(|id| id.0.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let method = req.method().clone();
+    let path = req.uri().path().to_owned();
+
+    match AssertUnwindSafe(next.run(req)).catch_unwind().await {
+        Ok(response) => response,
+        Err(payload) => {
+            error!(
+                request_id = %request_id,
+                method = %method,
+                path = path,
+                panic = panic_message(payload.as_ref()),
+                "Handler panicked"
+            );
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "status": "fail",
+                    "error": "Internal server error"
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+// LoadShedLayer's only error is `Overloaded`: every concurrency permit is taken
+pub async fn shed_response(_: tower::BoxError) -> Response {
+    AppError::overloaded().into_response()
+}
+
+// Replaces the empty body of a timed out request with our JSON error envelope
+pub async fn timeout_response(response: Response) -> Response {
+    if response.status() != StatusCode::GATEWAY_TIMEOUT {
+        return response;
+    }
+
+    (
+        StatusCode::GATEWAY_TIMEOUT,
+        Json(json!({
+            "status": "fail",
+            "error": "Request timed out"
+        })),
+    )
+        .into_response()
+}
+
+// tower-http answers CORS preflights with 200; browsers expect the conventional 204
+pub async fn preflight_no_content(req: Request, next: Next) -> Response {
+    let is_preflight = req.method() == Method::OPTIONS
+        && req.headers().contains_key(header::ACCESS_CONTROL_REQUEST_METHOD);
+
+    let mut response = next.run(req).await;
+    if is_preflight && response.status() == StatusCode::OK {
+        *response.status_mut() = StatusCode::NO_CONTENT;
+    }
+    response
+}
+
+// Bodied POST/PUT/PATCH requests must be JSON; bodyless ones (e.g. disable/enable) pass through
+pub async fn require_json(req: Request, next: Next) -> Response {
+    let has_body = req.body().size_hint().exact() != Some(0);
+    let needs_json = matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH) && has_body;
+
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+
+    if needs_json && !is_json {
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Json(json!({
+                "status": "fail",
+                "error": "Content-Type must be application/json"
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
@@ -0,0 +1,106 @@
+//! Request-scoped database transactions.
+//!
+//! Handlers opt in by taking a [`Tx`] argument and having their route wrapped in
+//! the [`transaction`] middleware:
+//!
+//! ```ignore
+//! .route("/api/tasks/reorder", post(reorder_tasks).layer(middleware::from_fn_with_state(state.clone(), transaction)))
+//! ```
+//!
+//! The transaction begins on the first [`Tx::conn`] call, so a handler that is still
+//! reading or validating its body holds no pool connection, and one that rejects the
+//! request before touching the database never opens a transaction. It is committed
+//! when the handler responds with a 2xx status, otherwise rolled back. Run queries on
+//! it with `.execute(tx.conn().await?)`; there is no `begin`/`commit` in the handler.
+
+use crate::models::{error::AppError, state::AppState};
+use axum::{
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+use tracing::{debug, error, warn};
+
+type TxCell = Arc<Mutex<Option<Transaction<'static, Postgres>>>>;
+
+// Inserted by `transaction`, filled in lazily by the first `Tx::conn` call
+#[derive(Clone)]
+struct TxSlot {
+    db: PgPool,
+    tx: TxCell,
+}
+
+/// Middleware that commits the request's [`Tx`] on a 2xx response and rolls it back otherwise
+pub async fn transaction(State(state): State<Arc<AppState>>, mut req: Request, next: Next) -> Response {
+    let slot = TxSlot {
+        db: state.db.clone(),
+        tx: TxCell::default(),
+    };
+    req.extensions_mut().insert(slot.clone());
+
+    let response = next.run(req).await;
+
+    // The handler has returned, so its `Tx` guard is dropped and the lock is free
+    let Some(tx) = slot.tx.lock().await.take() else {
+        return response;
+    };
+    if response.status().is_success() {
+        if let Err(e) = tx.commit().await {
+            return AppError::db(&state.db, e, "Failed to commit transaction").into_response();
+        }
+        debug!("Request transaction committed");
+    } else if let Err(e) = tx.rollback().await {
+        // The client already gets the handler's error; the connection is discarded either way
+        warn!(error = %e, status = %response.status(), "Failed to roll back request transaction");
+    } else {
+        debug!(status = %response.status(), "Request transaction rolled back");
+    }
+    response
+}
+
+/// The request's transaction, begun on first use
+pub struct Tx {
+    db: PgPool,
+    tx: OwnedMutexGuard<Option<Transaction<'static, Postgres>>>,
+}
+
+impl Tx {
+    /// The transaction's connection for running queries, beginning the transaction on the first call
+    pub async fn conn(&mut self) -> Result<&mut PgConnection, AppError> {
+        if self.tx.is_none() {
+            let tx = self
+                .db
+                .begin()
+                .await
+                .map_err(|e| AppError::db(&self.db, e, "Failed to begin transaction"))?;
+            *self.tx = Some(tx);
+        }
+        Ok(self.tx.as_deref_mut().expect("transaction begun above"))
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for Tx {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(slot) = parts.extensions.get::<TxSlot>().cloned() else {
+            error!(path = %parts.uri.path(), "Tx extracted on a route without the transaction middleware");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Internal server error"})),
+            )
+                .into());
+        };
+
+        Ok(Tx {
+            tx: slot.tx.lock_owned().await,
+            db: slot.db,
+        })
+    }
+}
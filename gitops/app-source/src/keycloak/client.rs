@@ -0,0 +1,418 @@
+use crate::keycloak::{KeycloakClient, KeycloakSession, KeycloakUser};
+use crate::models::{
+    config::{AdminGrant, Config},
+    error::AppError,
+    role::Role,
+};
+use async_trait::async_trait;
+use axum::{http::StatusCode, Json};
+use serde_json::json;
+use std::time::Duration;
+use tracing::{info, warn, error, debug};
+use uuid::Uuid;
+
+pub struct ReqwestKeycloakClient {
+    // One client for all Keycloak calls so connections and TLS sessions are reused
+    http: reqwest::Client,
+    keycloak_url: String,
+    realm: String,
+    admin_client_id: String,
+    admin_client_secret: String,
+    admin_grant: AdminGrant,
+}
+
+impl ReqwestKeycloakClient {
+    pub fn new(config: &Config) -> Result<Self, reqwest::Error> {
+        let http = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(config.keycloak_connect_timeout_secs))
+            .timeout(Duration::from_secs(config.keycloak_timeout_secs))
+            .build()?;
+
+        Ok(Self {
+            http,
+            keycloak_url: config.keycloak_url.clone(),
+            realm: config.realm.clone(),
+            admin_client_id: config.admin_client_id.clone(),
+            admin_client_secret: config.admin_client_secret.clone(),
+            admin_grant: config.admin_grant.clone(),
+        })
+    }
+
+    fn admin_url(&self, path: &str) -> String {
+        format!("{}/admin/realms/{}{}", self.keycloak_url, self.realm, path)
+    }
+}
+
+fn request_failed(e: reqwest::Error, message: &str) -> AppError {
+    if e.is_timeout() {
+        return AppError::keycloak_timed_out(&e);
+    }
+    error!(error = %e, "{}", message);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"status": "fail", "error": message, "details": e.to_string()})),
+    )
+        .into()
+}
+
+// Maps a non-success Keycloak response for a single user onto our error envelope
+async fn user_request_failed(res: reqwest::Response, id: Uuid, action: &str) -> AppError {
+    if res.status() == StatusCode::NOT_FOUND {
+        warn!(
+            target_user_id = %id,
+            "User not found in Keycloak for {}", action
+        );
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({"status": "fail", "error": "User not found in Keycloak"})),
+        )
+            .into();
+    }
+
+    let status = res.status();
+    let text = res.text().await.unwrap_or_else(|_| "<no body>".to_string());
+    // e.g. "User exists with same email", or "User email missing" when emailing a user
+    if status == StatusCode::CONFLICT || status == StatusCode::BAD_REQUEST {
+        let message = serde_json::from_str::<serde_json::Value>(&text)
+            .ok()
+            .and_then(|body| body["errorMessage"].as_str().map(str::to_string))
+            .unwrap_or(text);
+        warn!(
+            target_user_id = %id,
+            error = %message,
+            "Keycloak rejected {} user", action
+        );
+        return (
+            status,
+            Json(json!({"status": "fail", "error": message})),
+        )
+            .into();
+    }
+
+    error!(
+        target_user_id = %id,
+        status = %status,
+        body = %text,
+        "Keycloak API error when {} user", action
+    );
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({"status": "fail", "error": "Keycloak API error", "details": text})),
+    )
+        .into()
+}
+
+#[async_trait]
+impl KeycloakClient for ReqwestKeycloakClient {
+    async fn ping(&self) -> Result<(), AppError> {
+        // The same discovery document the auth layer fetches on startup
+        let url = format!(
+            "{}/realms/{}/.well-known/openid-configuration",
+            self.keycloak_url,
+            self.realm
+        );
+
+        let res = self.http.get(&url)
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to reach Keycloak"))?;
+
+        if !res.status().is_success() {
+            error!(status = %res.status(), "Keycloak discovery endpoint returned an error");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Keycloak API error"})),
+            )
+                .into());
+        }
+
+        Ok(())
+    }
+
+    async fn get_admin_token(&self) -> Result<String, AppError> {
+        debug!("Requesting admin token from Keycloak");
+
+        let url = format!(
+            "{}/realms/{}/protocol/openid-connect/token",
+            self.keycloak_url,
+            self.realm
+        );
+        let mut params = std::collections::HashMap::new();
+        params.insert("client_id", self.admin_client_id.clone());
+        match &self.admin_grant {
+            AdminGrant::ClientCredentials => {
+                params.insert("grant_type", "client_credentials".to_string());
+                params.insert("client_secret", self.admin_client_secret.clone());
+            }
+            AdminGrant::Password { username, password } => {
+                params.insert("grant_type", "password".to_string());
+                params.insert("username", username.clone());
+                params.insert("password", password.clone());
+                if !self.admin_client_secret.is_empty() {
+                    params.insert("client_secret", self.admin_client_secret.clone());
+                }
+            }
+        }
+
+        let res = self.http.post(&url)
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to get admin token"))?;
+
+        if !res.status().is_success() {
+            error!(status = %res.status(), "Invalid admin credentials for Keycloak");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Invalid admin credentials"})),
+            )
+                .into());
+        }
+
+        let token_res: serde_json::Value = res.json().await.map_err(|e| (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"status": "fail", "error": "Failed to parse token", "details": e.to_string()})),
+        ))?;
+
+        token_res["access_token"]
+            .as_str()
+            .map(|t| t.to_string())
+            .ok_or_else(|| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({"status": "fail", "error": "No access token in response"})),
+                )
+                    .into()
+            })
+    }
+
+    async fn list_users(&self, role: Option<&Role>) -> Result<Vec<KeycloakUser>, AppError> {
+        let token = self.get_admin_token().await?;
+
+        // Keycloak's base user representation carries no roles, so filter via the role's member list
+        let url = match role {
+            Some(role) => self.admin_url(&format!("/roles/{}/users", role.realm_name())),
+            None => self.admin_url("/users"),
+        };
+
+        let res = self.http.get(&url)
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to fetch users from Keycloak"))?;
+
+        if !res.status().is_success() {
+            error!(status = %res.status(), "Keycloak API error when fetching users");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Keycloak API error"})),
+            )
+                .into());
+        }
+
+        Ok(res.json().await.map_err(|e| {
+            error!(error = %e, "Failed to parse users JSON from Keycloak");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Failed to parse users", "details": e.to_string()})),
+            )
+        })?)
+    }
+
+    async fn get_user(&self, id: Uuid) -> Result<KeycloakUser, AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.get(self.admin_url(&format!("/users/{}", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to fetch user from Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "fetching").await);
+        }
+
+        Ok(res.json().await.map_err(|e| {
+            error!(target_user_id = %id, error = %e, "Failed to parse user JSON from Keycloak");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Failed to parse user", "details": e.to_string()})),
+            )
+        })?)
+    }
+
+    async fn count_users(&self, email_verified: Option<bool>) -> Result<i64, AppError> {
+        let token = self.get_admin_token().await?;
+
+        let mut request = self.http.get(self.admin_url("/users/count"))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Accept", "application/json");
+        if let Some(verified) = email_verified {
+            request = request.query(&[("emailVerified", verified)]);
+        }
+
+        let res = request
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to count users in Keycloak"))?;
+
+        if !res.status().is_success() {
+            error!(status = %res.status(), "Keycloak API error when counting users");
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Keycloak API error"})),
+            )
+                .into());
+        }
+
+        Ok(res.json::<i64>().await.map_err(|e| {
+            error!(error = %e, "Failed to parse user count from Keycloak");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Failed to parse user count", "details": e.to_string()})),
+            )
+        })?)
+    }
+
+    async fn delete_user(&self, id: Uuid) -> Result<(), AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.delete(self.admin_url(&format!("/users/{}", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to delete user from Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "deleting").await);
+        }
+
+        info!(target_user_id = %id, "User deleted from Keycloak");
+        Ok(())
+    }
+
+    async fn set_user_enabled(&self, id: Uuid, enabled: bool) -> Result<(), AppError> {
+        let token = self.get_admin_token().await?;
+
+        // Keycloak merges partial representations, so only the flag is sent
+        let res = self.http.put(self.admin_url(&format!("/users/{}", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(&json!({"enabled": enabled}))
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to update user in Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "updating").await);
+        }
+
+        Ok(())
+    }
+
+    async fn update_user(&self, id: Uuid, representation: &serde_json::Value) -> Result<(), AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.put(self.admin_url(&format!("/users/{}", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(representation)
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to update user in Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "updating").await);
+        }
+
+        Ok(())
+    }
+
+    async fn send_verify_email(&self, id: Uuid) -> Result<(), AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.put(self.admin_url(&format!("/users/{}/send-verify-email", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to send verification email through Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "emailing").await);
+        }
+
+        info!(target_user_id = %id, "Verification email sent through Keycloak");
+        Ok(())
+    }
+
+    async fn execute_actions_email(&self, id: Uuid, actions: &[&str]) -> Result<(), AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.put(self.admin_url(&format!("/users/{}/execute-actions-email", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .json(actions)
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to send actions email through Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "emailing").await);
+        }
+
+        info!(target_user_id = %id, actions = ?actions, "Actions email sent through Keycloak");
+        Ok(())
+    }
+
+    async fn list_user_sessions(&self, id: Uuid) -> Result<Vec<KeycloakSession>, AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.get(self.admin_url(&format!("/users/{}/sessions", id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to fetch sessions from Keycloak"))?;
+
+        if !res.status().is_success() {
+            return Err(user_request_failed(res, id, "listing sessions of").await);
+        }
+
+        Ok(res.json().await.map_err(|e| {
+            error!(error = %e, "Failed to parse sessions JSON from Keycloak");
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Failed to parse sessions", "details": e.to_string()})),
+            )
+        })?)
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<(), AppError> {
+        let token = self.get_admin_token().await?;
+
+        let res = self.http.delete(self.admin_url(&format!("/sessions/{}", session_id)))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await
+            .map_err(|e| request_failed(e, "Failed to revoke session in Keycloak"))?;
+
+        // Keycloak answers 204 even for unknown sessions, so only real errors fail here
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_else(|_| "<no body>".to_string());
+            error!(
+                session_id = %session_id,
+                status = %status,
+                body = %text,
+                "Keycloak API error when revoking session"
+            );
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Keycloak API error", "details": text})),
+            )
+                .into());
+        }
+
+        info!(session_id = %session_id, "Session revoked in Keycloak");
+        Ok(())
+    }
+}
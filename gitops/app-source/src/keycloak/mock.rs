@@ -0,0 +1,276 @@
+use crate::keycloak::{KeycloakClient, KeycloakSession, KeycloakUser};
+use crate::models::{error::AppError, role::Role};
+use async_trait::async_trait;
+use axum::{http::StatusCode, Json};
+use serde_json::json;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+/// In-memory stand-in for Keycloak used by tests.
+///
+/// Users are stored as raw admin API representations together with their
+/// realm role. `set_unavailable(true)` makes every call fail the way an
+/// erroring Keycloak would.
+#[derive(Default)]
+pub struct MockKeycloakClient {
+    users: Mutex<Vec<(serde_json::Value, Role)>>,
+    sessions: Mutex<Vec<(Uuid, serde_json::Value)>>,
+    emails: Mutex<Vec<(Uuid, String)>>,
+    unavailable: AtomicBool,
+    role_lookup_unavailable: AtomicBool,
+    requests: AtomicUsize,
+    listing_gate: Mutex<Option<Arc<Notify>>>,
+}
+
+impl MockKeycloakClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_user(self, representation: serde_json::Value, role: Role) -> Self {
+        self.users.lock().unwrap().push((representation, role));
+        self
+    }
+
+    /// Adds a session representation belonging to `user_id`
+    pub fn with_session(self, user_id: Uuid, representation: serde_json::Value) -> Self {
+        self.sessions.lock().unwrap().push((user_id, representation));
+        self
+    }
+
+    pub fn session_ids(&self) -> Vec<String> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(_, s)| s["id"].as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Emails Keycloak was asked to send so far, as (user id, `VERIFY_EMAIL` or the comma-joined actions)
+    pub fn sent_emails(&self) -> Vec<(Uuid, String)> {
+        self.emails.lock().unwrap().clone()
+    }
+
+    // Like Keycloak, refuses to email unknown users and users without an address
+    fn send_email(&self, id: Uuid, kind: &str) -> Result<(), AppError> {
+        let user = self.user(id).ok_or_else(Self::not_found)?;
+        if !user["email"].is_string() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({"status": "fail", "error": "User email missing"})),
+            )
+                .into());
+        }
+        self.emails.lock().unwrap().push((id, kind.to_string()));
+        Ok(())
+    }
+
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.unavailable.store(unavailable, Ordering::SeqCst);
+    }
+
+    /// Makes only role-filtered user listings fail, as when a role lookup errors
+    pub fn set_role_lookup_unavailable(&self, unavailable: bool) {
+        self.role_lookup_unavailable.store(unavailable, Ordering::SeqCst);
+    }
+
+    /// Makes user listings read the users, then wait for the returned notify before answering,
+    /// so tests can change a user while a listing is in flight
+    pub fn hold_user_listings(&self) -> Arc<Notify> {
+        let gate = Arc::new(Notify::new());
+        *self.listing_gate.lock().unwrap() = Some(gate.clone());
+        gate
+    }
+
+    /// Number of calls made against the mock so far
+    pub fn request_count(&self) -> usize {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    pub fn user(&self, id: Uuid) -> Option<serde_json::Value> {
+        let id = id.to_string();
+        self.users
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(u, _)| u["id"] == id.as_str())
+            .map(|(u, _)| u.clone())
+    }
+
+    fn check_available(&self) -> Result<(), AppError> {
+        self.requests.fetch_add(1, Ordering::SeqCst);
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Keycloak API error"})),
+            )
+                .into());
+        }
+        Ok(())
+    }
+
+    fn not_found() -> AppError {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({"status": "fail", "error": "User not found in Keycloak"})),
+        )
+            .into()
+    }
+}
+
+#[async_trait]
+impl KeycloakClient for MockKeycloakClient {
+    async fn ping(&self) -> Result<(), AppError> {
+        self.check_available()
+    }
+
+    async fn get_admin_token(&self) -> Result<String, AppError> {
+        self.check_available()?;
+        Ok("mock-admin-token".to_string())
+    }
+
+    async fn list_users(&self, role: Option<&Role>) -> Result<Vec<KeycloakUser>, AppError> {
+        self.check_available()?;
+        if role.is_some() && self.role_lookup_unavailable.load(Ordering::SeqCst) {
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Keycloak API error"})),
+            )
+                .into());
+        }
+        let users = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, r)| role.is_none_or(|role| role == r))
+            .map(|(u, _)| {
+                serde_json::from_value(u.clone()).map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({"status": "fail", "error": "Failed to parse users", "details": e.to_string()})),
+                    )
+                        .into()
+                })
+            })
+            .collect();
+        let gate = self.listing_gate.lock().unwrap().take();
+        if let Some(gate) = gate {
+            gate.notified().await;
+        }
+        users
+    }
+
+    async fn get_user(&self, id: Uuid) -> Result<KeycloakUser, AppError> {
+        self.check_available()?;
+        let user = self.user(id).ok_or_else(Self::not_found)?;
+        serde_json::from_value(user).map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"status": "fail", "error": "Failed to parse user", "details": e.to_string()})),
+            )
+                .into()
+        })
+    }
+
+    async fn count_users(&self, email_verified: Option<bool>) -> Result<i64, AppError> {
+        self.check_available()?;
+        let users = self.users.lock().unwrap();
+        let count = users
+            .iter()
+            .filter(|(u, _)| {
+                email_verified.is_none_or(|v| u["emailVerified"].as_bool().unwrap_or(false) == v)
+            })
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn delete_user(&self, id: Uuid) -> Result<(), AppError> {
+        self.check_available()?;
+        let id = id.to_string();
+        let mut users = self.users.lock().unwrap();
+        let before = users.len();
+        users.retain(|(u, _)| u["id"] != id.as_str());
+        if users.len() == before {
+            return Err(Self::not_found());
+        }
+        Ok(())
+    }
+
+    async fn set_user_enabled(&self, id: Uuid, enabled: bool) -> Result<(), AppError> {
+        self.check_available()?;
+        let id = id.to_string();
+        let mut users = self.users.lock().unwrap();
+        let (user, _) = users
+            .iter_mut()
+            .find(|(u, _)| u["id"] == id.as_str())
+            .ok_or_else(Self::not_found)?;
+        user["enabled"] = json!(enabled);
+        Ok(())
+    }
+
+    async fn update_user(&self, id: Uuid, representation: &serde_json::Value) -> Result<(), AppError> {
+        self.check_available()?;
+        let id = id.to_string();
+        let mut users = self.users.lock().unwrap();
+        if !users.iter().any(|(u, _)| u["id"] == id.as_str()) {
+            return Err(Self::not_found());
+        }
+        let email = &representation["email"];
+        if email.is_string() && users.iter().any(|(u, _)| u["id"] != id.as_str() && u["email"] == *email) {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({"status": "fail", "error": "User exists with same email"})),
+            )
+                .into());
+        }
+        let (user, _) = users.iter_mut().find(|(u, _)| u["id"] == id.as_str()).ok_or_else(Self::not_found)?;
+        for (key, value) in representation.as_object().into_iter().flatten() {
+            user[key] = value.clone();
+        }
+        Ok(())
+    }
+
+    async fn send_verify_email(&self, id: Uuid) -> Result<(), AppError> {
+        self.check_available()?;
+        self.send_email(id, "VERIFY_EMAIL")
+    }
+
+    async fn execute_actions_email(&self, id: Uuid, actions: &[&str]) -> Result<(), AppError> {
+        self.check_available()?;
+        self.send_email(id, &actions.join(","))
+    }
+
+    async fn list_user_sessions(&self, id: Uuid) -> Result<Vec<KeycloakSession>, AppError> {
+        self.check_available()?;
+        self.sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(user_id, _)| *user_id == id)
+            .map(|(_, s)| {
+                serde_json::from_value(s.clone()).map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({"status": "fail", "error": "Failed to parse sessions", "details": e.to_string()})),
+                    )
+                        .into()
+                })
+            })
+            .collect()
+    }
+
+    async fn delete_session(&self, session_id: &str) -> Result<(), AppError> {
+        self.check_available()?;
+        self.sessions
+            .lock()
+            .unwrap()
+            .retain(|(_, s)| s["id"] != session_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+pub mod cache;
+pub mod client;
+pub mod mock;
+pub mod session;
+pub mod user;
+
+use crate::models::{error::AppError, role::Role};
+use async_trait::async_trait;
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+pub use cache::CachedKeycloakClient;
+pub use client::ReqwestKeycloakClient;
+pub use mock::MockKeycloakClient;
+pub use session::KeycloakSession;
+pub use user::KeycloakUser;
+
+/// Operations the API performs against the Keycloak admin REST API.
+///
+/// Handlers only talk to Keycloak through this trait so they can be tested
+/// against [`MockKeycloakClient`] instead of a live server.
+#[async_trait]
+pub trait KeycloakClient: Send + Sync {
+    /// Cheap reachability check against the realm's OpenID discovery endpoint
+    async fn ping(&self) -> Result<(), AppError>;
+
+    async fn get_admin_token(&self) -> Result<String, AppError>;
+
+    /// Base user representations, optionally limited to members of a realm role
+    async fn list_users(&self, role: Option<&Role>) -> Result<Vec<KeycloakUser>, AppError>;
+
+    /// A single user; `404` if Keycloak doesn't know the id
+    async fn get_user(&self, id: Uuid) -> Result<KeycloakUser, AppError>;
+
+    async fn count_users(&self, email_verified: Option<bool>) -> Result<i64, AppError>;
+
+    async fn delete_user(&self, id: Uuid) -> Result<(), AppError>;
+
+    async fn set_user_enabled(&self, id: Uuid, enabled: bool) -> Result<(), AppError>;
+
+    /// Merges a partial `UserRepresentation` into the user; `409` when Keycloak reports a
+    /// conflict, e.g. an email another user already has
+    async fn update_user(&self, id: Uuid, representation: &serde_json::Value) -> Result<(), AppError>;
+
+    /// Asks Keycloak to email the user a link to verify their address
+    async fn send_verify_email(&self, id: Uuid) -> Result<(), AppError>;
+
+    /// Emails the user a link to perform required actions, e.g. `UPDATE_PASSWORD`
+    async fn execute_actions_email(&self, id: Uuid, actions: &[&str]) -> Result<(), AppError>;
+
+    /// Active sessions of a user; empty when they aren't logged in anywhere
+    async fn list_user_sessions(&self, id: Uuid) -> Result<Vec<KeycloakSession>, AppError>;
+
+    /// Logs out a single session, revoking its tokens
+    async fn delete_session(&self, session_id: &str) -> Result<(), AppError>;
+}
+
+/// `client` behind a [`CachedKeycloakClient`] when `USER_LIST_CACHE_SECS` is above zero
+pub fn with_user_list_cache(client: Arc<dyn KeycloakClient>, ttl_secs: u64) -> Arc<dyn KeycloakClient> {
+    match ttl_secs {
+        0 => client,
+        secs => Arc::new(CachedKeycloakClient::new(client, Duration::from_secs(secs))),
+    }
+}
@@ -0,0 +1,39 @@
+use crate::models::{response::UserResponse, role::Role};
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// The fields we use from Keycloak's admin API `UserRepresentation`.
+/// Everything except the id may be missing, depending on realm settings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeycloakUser {
+    pub id: Uuid,
+    pub username: Option<String>,
+    pub email: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub created_timestamp: Option<i64>,
+    pub enabled: Option<bool>,
+    pub email_verified: Option<bool>,
+}
+
+impl KeycloakUser {
+    /// `role` is resolved by the caller from the admin role's members, since
+    /// the base representation doesn't carry roles.
+    pub fn into_response(self, role: &Role) -> UserResponse {
+        let created_at = self
+            .created_timestamp
+            .and_then(|ts| Utc.timestamp_millis_opt(ts).single());
+
+        UserResponse {
+            id: self.id,
+            name: self.username.unwrap_or_else(|| "unknown".to_string()),
+            email: self.email.unwrap_or_default(),
+            role: role.to_string(),
+            verified: self.email_verified.unwrap_or(false),
+            created_at,
+            updated_at: created_at,
+            role_resolution_error: false,
+        }
+    }
+}
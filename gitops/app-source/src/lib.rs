@@ -0,0 +1,9 @@
+pub mod handlers;
+pub mod jobs;
+pub mod keycloak;
+pub mod maintenance;
+pub mod models;
+pub mod monitoring;
+pub mod postman;
+pub mod queries;
+pub mod routes;
@@ -1,40 +1,84 @@
-use axum::serve;
+use axum::{serve, Router};
+use axum_server::tls_rustls::RustlsConfig;
 use reqwest::Url;
-use sqlx::PgPool;
-use std::sync::Arc;
+use sqlx::postgres::PgPoolOptions;
+use std::{
+    net::SocketAddr,
+    sync::{atomic::AtomicBool, Arc},
+    time::Duration,
+};
 use tokio::net::TcpListener;
-use utoipa::OpenApi;
-use utoipa_swagger_ui::SwaggerUi;
+use utoipa::{openapi::server::Server, OpenApi};
 
-mod handlers;
-mod models;
-mod routes;
-
-use crate::models::{config::Config, state::AppState, logging::LoggingConfig};
+use task_api::{handlers, models, routes};
+use task_api::handlers::fallback::not_found;
+use task_api::keycloak::{with_user_list_cache, ReqwestKeycloakClient};
+use task_api::jobs::Jobs;
+use task_api::maintenance::MaintenanceSync;
+use task_api::monitoring::{warm_up_pool, PoolStatsReporter};
+use task_api::models::{
+    config::{redact_url_password, Config, ConfigError},
+    logging::LoggingConfig,
+    state::AppState,
+};
 use axum_keycloak_auth::instance::{KeycloakAuthInstance, KeycloakConfig};
-use tracing::{info, error};
+use tracing::{info, error, warn};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         handlers::task::create_task,
         handlers::task::list_tasks,
+        handlers::task::recent_tasks,
+        handlers::task::search_tasks,
+        handlers::task::reorder_tasks,
         handlers::task::delete_task,
+        handlers::export::export_tasks,
+        handlers::import::import_tasks,
+        handlers::session::validate_token,
+        handlers::session::list_sessions,
+        handlers::session::revoke_session,
         handlers::user::list_users,
+        handlers::user::get_user,
+        handlers::user::update_user,
         handlers::user::delete_user,
+        handlers::user::disable_user,
+        handlers::user::enable_user,
+        handlers::user::send_verify_email,
+        handlers::user::send_reset_password_email,
+        handlers::admin::admin_stats,
+        handlers::admin::admin_list_tasks,
+        handlers::admin::admin_delete_task,
+        handlers::admin::admin_user_tasks,
+        handlers::admin::maintenance_status,
+        handlers::admin::set_maintenance,
         handlers::health::health,
+        handlers::health::ready,
     ),
     components(
         schemas(
             models::task::Task,
             models::task::CreateTaskSchema,
+            models::task::ReorderTasksSchema,
+            models::task::TaskSearchSchema,
+            models::task::CreatedRange,
             models::response::UserResponse,
+            models::user::UpdateUserSchema,
             models::response::TaskResponse,
             models::response::TaskListResponse,
+            models::response::ActionMessage,
+            models::response::SessionResponse,
+            models::response::TokenValidity,
+            models::response::AdminStats,
+            models::response::MaintenanceStatus,
+            models::response::TaskPage,
+            models::response::ImportSummary,
+            models::response::ImportRowError,
         )
     ),
     tags(
         (name = "tasks", description = "Task management endpoints"),
+        (name = "auth", description = "The caller's token and Keycloak sessions"),
         (name = "users", description = "User management endpoints (admin only)"),
         (name = "health", description = "Check app health"),
     ),
@@ -62,30 +106,173 @@ impl utoipa::Modify for SecurityAddon {
     }
 }
 
+/// `--check-config`: validate config and probe dependencies without serving, exiting 0 or 1
+async fn check_config(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    println!("Configuration: OK");
+
+    let db = PgPoolOptions::new()
+        .acquire_timeout(Duration::from_secs(5))
+        .connect_lazy(&config.database_url)?;
+    let db_read = match &config.database_read_url {
+        Some(url) => PgPoolOptions::new()
+            .acquire_timeout(Duration::from_secs(5))
+            .connect_lazy(url)?,
+        None => db.clone(),
+    };
+    let keycloak = Arc::new(ReqwestKeycloakClient::new(&config)?);
+    let maintenance = Arc::new(AtomicBool::new(config.maintenance_mode));
+    let state = AppState { db, db_read, config, keycloak, maintenance };
+
+    let report = handlers::health::check_dependencies(&state).await;
+    println!("{}", serde_json::to_string_pretty(&report.to_json())?);
+
+    std::process::exit(if report.all_up() { 0 } else { 1 });
+}
+
+// How long background jobs get to finish once the server has stopped
+const JOBS_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Resolves on Ctrl+C or SIGTERM (what Kubernetes sends before killing the pod)
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to install SIGTERM handler: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("Shutdown signal received");
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let check_only = std::env::args().skip(1).any(|arg| arg == "--check-config");
+
     // Initialize logging first, before any other operations
-    let logging_config = LoggingConfig::from_env();
+    let (logging_config, logging_problems) = LoggingConfig::from_env();
     let _guard = logging_config.init();
     
     info!("Starting Task API server");
     
-    let config = Config::init();
+    // Logging problems are reported alongside the rest rather than on their own
+    let config = match Config::init() {
+        Ok(config) if logging_problems.is_empty() => config,
+        result => {
+            let mut problems = logging_problems;
+            problems.extend(result.err().into_iter().flat_map(|e| e.problems));
+            let e = ConfigError { problems };
+            error!(problems = ?e.problems, "Invalid configuration");
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
     info!("Configuration loaded successfully");
+    models::role::set_admin_role_name(&config.admin_role_name);
+    models::logging::set_base_path(&config.base_path);
+
+    if check_only {
+        return check_config(config).await;
+    }
 
     info!("Connecting to database");
-    let db = PgPool::connect(&config.database_url).await.map_err(|e| {
-        error!("Failed to connect to database: {}", e);
-        e
-    })?;
+    let db = PgPoolOptions::new()
+        .min_connections(config.db_min_connections)
+        .connect(&config.database_url)
+        .await
+        .map_err(|e| {
+            error!(
+                database_url = %redact_url_password(&config.database_url),
+                "Failed to connect to database: {}", e
+            );
+            e
+        })?;
     info!("Database connection established");
+
+    if config.db_pool_warmup {
+        let elapsed = warm_up_pool(&db, config.db_min_connections).await.map_err(|e| {
+            error!(connections = config.db_min_connections, "Failed to warm up database pool: {}", e);
+            e
+        })?;
+        info!(
+            connections = config.db_min_connections,
+            duration_ms = elapsed.as_millis() as u64,
+            "Database pool warmed up"
+        );
+    }
+
+    let db_read = match &config.database_read_url {
+        Some(url) => {
+            let db_read = PgPoolOptions::new()
+                .min_connections(config.db_min_connections)
+                .connect(url)
+                .await
+                .map_err(|e| {
+                    error!(
+                        database_read_url = %redact_url_password(url),
+                        "Failed to connect to read replica: {}", e
+                    );
+                    e
+                })?;
+            info!("Read replica connection established");
+            db_read
+        }
+        None => db.clone(),
+    };
+
+    let keycloak = with_user_list_cache(
+        Arc::new(ReqwestKeycloakClient::new(&config)?),
+        config.user_list_cache_secs,
+    );
     
     let state = Arc::new(AppState {
         db,
+        db_read,
         config: config.clone(),
+        keycloak,
+        maintenance: Arc::new(AtomicBool::new(config.maintenance_mode)),
     });
     info!("Application state initialized");
 
+    if state.config.maintenance_mode {
+        warn!("Starting in maintenance mode: user routes answer 503 until it is turned off, unless an admin already has");
+    }
+
+    if state.config.dev_mode {
+        warn!(
+            dev_user_id = %handlers::dev_auth::DEV_USER_ID,
+            "DEV_MODE IS ENABLED: user routes accept requests without a valid token. Never use this outside local development"
+        );
+    }
+
+    let mut jobs = Jobs::new();
+    jobs.register(MaintenanceSync::new(
+        state.db.clone(),
+        state.maintenance.clone(),
+        state.config.maintenance_mode,
+        Duration::from_secs(state.config.maintenance_poll_secs),
+    ));
+    if let Some(secs) = state.config.pool_stats_interval_secs.filter(|secs| *secs > 0) {
+        jobs.register(PoolStatsReporter::new(state.db.clone(), Duration::from_secs(secs)));
+    }
+    if let Some(retention) = logging_config.retention() {
+        jobs.register(retention);
+    }
+
     // Initialize Keycloak instance for auth
     info!("Initializing Keycloak authentication");
     let keycloak_config = KeycloakConfig::builder()
@@ -96,8 +283,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let keycloak_instance = Arc::new(KeycloakAuthInstance::new(keycloak_config));
     info!("Keycloak authentication initialized");
 
-    let app = routes::create_routes(state.clone(), keycloak_instance)
-        .merge(SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", ApiDoc::openapi()));
+    let base_path = state.config.base_path.clone();
+    let mut openapi = ApiDoc::openapi();
+    if !state.config.public_base_urls.is_empty() {
+        openapi.servers = Some(
+            state
+                .config
+                .public_base_urls
+                .iter()
+                .map(|url| Server::new(format!("{}{}", url, base_path)))
+                .collect(),
+        );
+    } else if !base_path.is_empty() {
+        // Relative server URL so generated clients prefix every path with BASE_PATH
+        openapi.servers = Some(vec![Server::new(base_path.clone())]);
+    }
+
+    let api = routes::create_routes(state.clone(), keycloak_instance);
+    let api = if base_path.is_empty() {
+        api
+    } else {
+        Router::new().nest(&base_path, api).fallback(not_found)
+    };
+    let swagger_url = format!("{}{}", base_path, state.config.swagger_path);
+    let app = api.merge(routes::docs_routes(&state.config, openapi));
 
     let addr = format!("{}:{}", state.config.host, state.config.port);
     let listener = TcpListener::bind(&addr).await.map_err(|e| {
@@ -105,21 +314,94 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         e
     })?;
     
+    // Pick the crypto provider explicitly; other crates in the tree may enable ring as well
+    let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
+    let tls = match (&state.config.tls_cert_path, &state.config.tls_key_path) {
+        (Some(cert), Some(key)) => Some(RustlsConfig::from_pem_file(cert, key).await.map_err(|e| {
+            error!(cert = %cert, key = %key, "Failed to load TLS certificate: {}", e);
+            e
+        })?),
+        _ => None,
+    };
+    let http2_enabled = state.config.http2_enabled;
+    let scheme = if tls.is_some() { "https" } else { "http" };
+
+    // One grep-able line with the effective settings; never add secrets or the raw DATABASE_URL here
     info!(
-        address = %addr,
-        "Task API server listening"
+        version = env!("CARGO_PKG_VERSION"),
+        bind_address = %addr,
+        base_path = %base_path,
+        tls = tls.is_some(),
+        http2 = http2_enabled,
+        db_host = %state.config.database_host(),
+        db_pool_max = state.db.options().get_max_connections(),
+        db_read_replica = state.config.database_read_url.is_some(),
+        log_level = %logging_config.level,
+        log_format = logging_config.format.as_str(),
+        auth_mode = if cfg!(feature = "test-auth") {
+            "test-auth"
+        } else if state.config.dev_mode {
+            "dev-mode"
+        } else {
+            "keycloak"
+        },
+        keycloak_url = %state.config.keycloak_url,
+        keycloak_realm = %state.config.realm,
+        request_timeout_secs = state.config.request_timeout_secs,
+        "Startup summary"
     );
+
     info!(
-        swagger_url = format!("http://{}/swagger-ui", addr),
-        "Swagger UI available"
+        address = %addr,
+        tls = tls.is_some(),
+        http2 = http2_enabled,
+        "Task API server listening"
     );
+    if state.config.swagger_enabled {
+        info!(
+            swagger_url = format!("{}://{}{}", scheme, addr, swagger_url),
+            basic_auth = state.config.docs_basic_auth.is_some(),
+            "Swagger UI available"
+        );
+    } else {
+        info!("Swagger UI and OpenAPI document disabled");
+    }
 
-    info!("Starting HTTP server");
-    serve(listener, app).await.map_err(|e| {
+    let result = match tls {
+        Some(tls) => {
+            if !http2_enabled {
+                // Advertise only http/1.1 so TLS clients never negotiate h2
+                let mut server_config = (*tls.get_inner()).clone();
+                server_config.alpn_protocols = vec![b"http/1.1".to_vec()];
+                tls.reload_from_config(Arc::new(server_config));
+            }
+            info!("Starting HTTPS server (TLS enabled)");
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown_signal().await;
+                shutdown_handle.graceful_shutdown(Some(Duration::from_secs(30)));
+            });
+            axum_server::from_tcp_rustls(listener.into_std()?, tls)
+                .handle(handle)
+                .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+                .await
+        }
+        // The protocol is detected per connection, so h2c prior-knowledge clients work alongside HTTP/1.1
+        None => {
+            info!("Starting HTTP server (TLS disabled)");
+            serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(shutdown_signal())
+                .await
+        }
+    };
+    result.map_err(|e| {
         error!("Server error: {}", e);
         e
     })?;
 
+    jobs.shutdown(JOBS_SHUTDOWN_TIMEOUT).await;
+
     info!("Server shutdown");
     Ok(())
 }
@@ -0,0 +1,70 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserListQuery {
+    /// Only return users with this role: `admin` for members of the admin realm role, `user` for everyone else
+    pub role: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminTaskListQuery {
+    /// Only return tasks owned by this user
+    pub user_id: Option<uuid::Uuid>,
+    /// 1-based page number (default 1)
+    pub page: Option<i64>,
+    /// Tasks per page (default 20, capped at 100)
+    pub per_page: Option<i64>,
+}
+
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// 1-based page and page size with the defaults and cap applied
+pub fn page_bounds(page: Option<i64>, per_page: Option<i64>) -> (i64, i64) {
+    (
+        page.unwrap_or(1).max(1),
+        per_page.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+    )
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageQuery {
+    /// 1-based page number (default 1)
+    pub page: Option<i64>,
+    /// Tasks per page (default 20, capped at 100)
+    pub per_page: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskExportQuery {
+    /// `csv` (default) or `json`
+    pub format: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskImportQuery {
+    /// Reject the whole upload if any row is invalid
+    pub strict: Option<bool>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecentTasksQuery {
+    /// How many tasks to return (default 5, capped at 50)
+    pub limit: Option<i64>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaskListQuery {
+    /// `position` for the manual order, or `created_at`; unordered when omitted
+    pub sort: Option<String>,
+    /// Comma-separated task fields to return, e.g. `id,name`; all fields when omitted
+    pub fields: Option<String>,
+}
@@ -1,10 +1,49 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use utoipa::{ToSchema, schema};
+use utoipa::ToSchema;
 
 use crate::models::task::Task;
 
+/// A successful delete: an empty 204, or 200 with `{"status": "success"}` for clients that
+/// can't handle a 204 (`DELETE_RETURNS_BODY`)
+pub fn deleted(with_body: bool) -> Response {
+    if with_body {
+        (StatusCode::OK, Json(serde_json::json!({"status": "success"}))).into_response()
+    } else {
+        StatusCode::NO_CONTENT.into_response()
+    }
+}
+
+/// The `{"status": "success", "data": ...}` envelope around every JSON payload
+#[derive(Serialize, ToSchema)]
+pub struct Success<T> {
+    #[schema(example = "success")]
+    pub status: &'static str,
+    pub data: T,
+}
+
+pub fn success<T>(data: T) -> Json<Success<T>> {
+    Json(Success { status: "success", data })
+}
+
+/// `data` of an action with nothing to return but a confirmation
+#[derive(Serialize, ToSchema)]
+pub struct ActionMessage {
+    #[schema(example = "User 0b7e3f4c-9a43-4d36-8f5e-2f1c6a1b9d20 disabled successfully")]
+    pub message: String,
+}
+
+/// `{"status": "success", "data": {"message": ...}}`
+pub fn message(text: impl Into<String>) -> Json<Success<ActionMessage>> {
+    success(ActionMessage { message: text.into() })
+}
+
 #[derive(Serialize, ToSchema)]
 pub struct UserResponse {
     pub id: Uuid,
@@ -18,6 +57,9 @@ pub struct UserResponse {
     #[serde(rename = "updatedAt")]
     #[schema(value_type = Option<String>, format = DateTime)]
     pub updated_at: Option<DateTime<Utc>>,
+    /// Set when the role couldn't be looked up in Keycloak and `role` is the `user` default
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub role_resolution_error: bool,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -26,6 +68,9 @@ pub struct TaskResponse {
     pub name: String,
     pub user_id: Uuid,
     pub description: Option<String>,
+    pub position: i32,
+    /// The `client_id` the task was created with, if any
+    pub client_id: Option<String>,
     #[serde(rename = "createdAt")]
     #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTime<Utc>,
@@ -34,6 +79,74 @@ pub struct TaskResponse {
     pub updated_at: DateTime<Utc>,
 }
 
+/// One of the caller's active Keycloak sessions
+#[derive(Serialize, ToSchema)]
+pub struct SessionResponse {
+    pub id: String,
+    pub ip_address: Option<String>,
+    #[serde(rename = "startedAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(rename = "lastAccessAt")]
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub last_access_at: Option<DateTime<Utc>>,
+    /// Client ids the session has logged in to
+    pub clients: Vec<String>,
+}
+
+/// `GET /api/auth/validate`: the bearer token passed validation. An invalid or expired token
+/// never gets this far, it's answered with 401 by the auth layer.
+#[derive(Serialize, ToSchema)]
+pub struct TokenValidity {
+    #[schema(example = true)]
+    pub valid: bool,
+    #[schema(value_type = String, format = DateTime)]
+    pub expires_at: DateTime<Utc>,
+    /// The token's `sub`, the caller's Keycloak user id
+    pub subject: String,
+}
+
+/// Body of `PUT /api/admin/maintenance` and of both maintenance responses
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct MaintenanceStatus {
+    /// Whether user routes currently answer 503 `maintenance`
+    pub enabled: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AdminStats {
+    pub total_users: i64,
+    pub verified_users: i64,
+    pub unverified_users: i64,
+    /// Tasks in the admin's tenant; the user counts cover the whole realm
+    pub total_tasks: i64,
+}
+
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportRowError {
+    /// 1-based line in the uploaded file
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportSummary {
+    pub imported: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+
+#[derive(Serialize, ToSchema)]
+pub struct TaskPage {
+    pub tasks: Vec<TaskResponse>,
+    pub page: i64,
+    pub per_page: i64,
+    /// Matching tasks across all pages
+    pub total: i64,
+}
+
 
 #[derive(Serialize, ToSchema)]
 pub struct TaskListResponse {
@@ -42,6 +155,45 @@ pub struct TaskListResponse {
 }
 
 
+/// Sparse fieldset from a `fields` query param, e.g. `id,name`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskFields(Vec<String>);
+
+impl TaskFields {
+    /// Serialized names of every `TaskResponse` field
+    pub const ALL: [&'static str; 8] =
+        ["id", "name", "user_id", "description", "position", "client_id", "createdAt", "updatedAt"];
+
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut fields = Vec::new();
+        for field in value.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+            if !Self::ALL.contains(&field) {
+                return Err(format!(
+                    "Unknown field '{}', expected any of: {}",
+                    field,
+                    Self::ALL.join(", ")
+                ));
+            }
+            if !fields.iter().any(|f| f == field) {
+                fields.push(field.to_string());
+            }
+        }
+        if fields.is_empty() {
+            return Err("fields must name at least one field".to_string());
+        }
+        Ok(TaskFields(fields))
+    }
+
+    /// Serializes `task` keeping only the requested fields
+    pub fn select(&self, task: TaskResponse) -> serde_json::Value {
+        let mut value = serde_json::to_value(task).unwrap_or_default();
+        if let serde_json::Value::Object(map) = &mut value {
+            map.retain(|key, _| self.0.contains(key));
+        }
+        value
+    }
+}
+
 impl From<Task> for TaskResponse {
   fn from(task: Task) -> Self {
       TaskResponse {
@@ -49,6 +201,8 @@ impl From<Task> for TaskResponse {
           name: task.name,
           user_id: task.user_id,
           description: task.description,
+          position: task.position,
+          client_id: task.client_id,
           created_at: task.created_at,
           updated_at: task.updated_at,
       }
@@ -1,21 +1,65 @@
-use std::fmt;
+use serde::Deserialize;
+use std::{fmt, str::FromStr, sync::OnceLock};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+const DEFAULT_ADMIN_ROLE_NAME: &str = "admin";
+
+static ADMIN_ROLE_NAME: OnceLock<String> = OnceLock::new();
+
+/// Sets the Keycloak realm role that grants [`Role::Admin`] (`ADMIN_ROLE_NAME`).
+/// Token roles are decoded through `From<String>`, which has no access to the
+/// config, so this is set once at startup before any request is served.
+pub fn set_admin_role_name(name: &str) {
+    let _ = ADMIN_ROLE_NAME.set(name.to_string());
+}
+
+pub fn admin_role_name() -> &'static str {
+    ADMIN_ROLE_NAME
+        .get()
+        .map(String::as_str)
+        .unwrap_or(DEFAULT_ADMIN_ROLE_NAME)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Role {
     User,
     Admin,
 }
 
+// Realm role names from tokens; only the configured admin role grants Admin
 impl From<String> for Role {
   fn from(s: String) -> Self {
-      match s.to_lowercase().as_str() {
-          "admin" => Role::Admin,
-          "user" => Role::User,
-          _ => Role::User,
+      if s.eq_ignore_ascii_case(admin_role_name()) {
+          Role::Admin
+      } else {
+          Role::User
       }
   }
 }
 
+impl Role {
+    /// The realm role's name in Keycloak, for admin API calls
+    pub fn realm_name(&self) -> &'static str {
+        match self {
+            Role::User => "user",
+            Role::Admin => admin_role_name(),
+        }
+    }
+}
+
+// Strict parsing of the app's role names for user input, unlike `From<String>` which falls back to `User`
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "user" => Ok(Role::User),
+            other => Err(format!("Invalid role '{}', expected one of: user, admin", other)),
+        }
+    }
+}
+
 impl fmt::Display for Role {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
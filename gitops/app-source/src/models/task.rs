@@ -1,7 +1,10 @@
 use sqlx::types::Uuid;
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
-use utoipa::{ToSchema, schema};
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::queries::TaskSort;
 
 #[derive(sqlx::FromRow, ToSchema)]
 pub struct Task {
@@ -9,14 +12,162 @@ pub struct Task {
     pub name: String,
     pub description: Option<String>,
     pub user_id: Uuid,
+    pub position: i32,
+    pub client_id: Option<String>,
     #[schema(value_type = String, format = DateTime)]
     pub created_at: DateTime<Utc>,
     #[schema(value_type = String, format = DateTime)]
     pub updated_at: DateTime<Utc>,
 }
 
+/// Longest accepted `client_id`, matching the column
+pub const CLIENT_ID_MAX_CHARS: usize = 64;
+
 #[derive(sqlx::FromRow, Deserialize, ToSchema)]
 pub struct CreateTaskSchema {
     pub name: String,
-    pub description: Option<String>
-}
\ No newline at end of file
+    pub description: Option<String>,
+    /// Id the client generated for its optimistic copy of the task, echoed back in the response
+    #[serde(default)]
+    #[schema(max_length = 64, example = "7f9c2a1e-local")]
+    pub client_id: Option<String>,
+}
+
+impl CreateTaskSchema {
+    /// Trims the name and client id and checks them and the description against their character limits
+    pub fn validate(&mut self, name_max_chars: usize, description_max_chars: usize) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err("name is required".to_string());
+        }
+        if self.name.chars().count() > name_max_chars {
+            return Err(format!("name must be at most {} characters", name_max_chars));
+        }
+        if self
+            .description
+            .as_ref()
+            .is_some_and(|d| d.chars().count() > description_max_chars)
+        {
+            return Err(format!("description must be at most {} characters", description_max_chars));
+        }
+        if let Some(client_id) = &mut self.client_id {
+            *client_id = client_id.trim().to_string();
+            if client_id.is_empty() {
+                return Err("client_id must not be blank".to_string());
+            }
+            if client_id.chars().count() > CLIENT_ID_MAX_CHARS {
+                return Err(format!("client_id must be at most {} characters", CLIENT_ID_MAX_CHARS));
+            }
+        }
+        Ok(())
+    }
+
+    /// True when the description repeats the (trimmed) name, usually a UI mistake
+    pub fn description_equals_name(&self) -> bool {
+        self.description.as_deref().is_some_and(|d| d.trim() == self.name)
+    }
+}
+
+/// What `create_task` does with a description identical to the name (`DESCRIPTION_EQUALS_NAME`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DescriptionNameRule {
+    #[default]
+    Off,
+    /// Accept the task, only logging the match
+    Warn,
+    /// Reject the task with a 400
+    Reject,
+}
+
+impl FromStr for DescriptionNameRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(DescriptionNameRule::Off),
+            "warn" => Ok(DescriptionNameRule::Warn),
+            "reject" => Ok(DescriptionNameRule::Reject),
+            other => Err(format!("Invalid rule '{}', expected one of: off, warn, reject", other)),
+        }
+    }
+}
+
+/// Body of `POST /api/tasks/search`; every criterion is optional and they combine with AND
+#[derive(Deserialize, ToSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct TaskSearchSchema {
+    /// Case-insensitive substring of the name or description
+    pub text: Option<String>,
+    pub created_range: Option<CreatedRange>,
+    /// `created_at` (default, newest first), `updated_at` (newest first), `position` or `name`
+    pub sort: Option<String>,
+    /// 1-based page number (default 1)
+    pub page: Option<i64>,
+    /// Tasks per page (default 20, capped at 100)
+    pub per_page: Option<i64>,
+    /// Not supported: tasks have no status. Sending it is a 400
+    #[schema(value_type = Option<String>)]
+    pub status: Option<serde_json::Value>,
+    /// Not supported: tasks have no priority. Sending it is a 400
+    #[schema(value_type = Option<String>)]
+    pub priority: Option<serde_json::Value>,
+    /// Not supported: tasks have no tags. Sending it is a 400
+    #[schema(value_type = Option<Vec<String>>)]
+    pub tags: Option<serde_json::Value>,
+}
+
+/// Inclusive bounds on the creation time; either end may be left open
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreatedRange {
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub from: Option<DateTime<Utc>>,
+    #[schema(value_type = Option<String>, format = DateTime)]
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl TaskSearchSchema {
+    /// The first criterion sent that tasks can't be filtered on, so it isn't silently ignored
+    pub fn unsupported_filter(&self) -> Option<&'static str> {
+        [("status", &self.status), ("priority", &self.priority), ("tags", &self.tags)]
+            .into_iter()
+            .find(|(_, value)| value.is_some())
+            .map(|(field, _)| field)
+    }
+
+    /// The requested ordering; anything outside `TaskSort` is rejected before reaching the SQL
+    pub fn sort(&self) -> Result<TaskSort, String> {
+        self.sort.as_deref().map_or(Ok(TaskSort::default()), str::parse)
+    }
+
+    /// `text` as an ILIKE pattern with its own `%`, `_` and `\` matched literally
+    pub fn text_pattern(&self) -> Option<String> {
+        let text = self.text.as_deref()?.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        Some(format!("%{}%", escaped))
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct ReorderTasksSchema {
+    /// The caller's task ids in their new order; unlisted tasks keep their relative order after these
+    pub task_ids: Vec<Uuid>,
+}
+
+impl ReorderTasksSchema {
+    /// Caps the id list at `max_items` (`MAX_BATCH_ITEMS`) and rejects repeated ids
+    pub fn validate(&self, max_items: usize) -> Result<(), String> {
+        if self.task_ids.len() > max_items {
+            return Err(format!("task_ids must contain at most {} ids", max_items));
+        }
+        let unique: std::collections::HashSet<_> = self.task_ids.iter().collect();
+        if unique.len() != self.task_ids.len() {
+            return Err("task_ids must not contain duplicates".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,52 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+
+/// Profile changes for `PATCH /api/admin/users/{id}`; fields left out stay as they are
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateUserSchema {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub email: Option<String>,
+    /// `false` suspends the user like `POST /api/admin/users/{id}/disable`
+    pub enabled: Option<bool>,
+}
+
+impl UpdateUserSchema {
+    /// Trims the text fields and rejects an empty update or an implausible email
+    pub fn validate(&mut self) -> Result<(), String> {
+        for field in [&mut self.first_name, &mut self.last_name, &mut self.email].into_iter().flatten() {
+            *field = field.trim().to_string();
+        }
+        if self.first_name.is_none() && self.last_name.is_none() && self.email.is_none() && self.enabled.is_none() {
+            return Err("at least one of first_name, last_name, email or enabled is required".to_string());
+        }
+        if self
+            .email
+            .as_deref()
+            .is_some_and(|email| !email.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && !domain.is_empty()))
+        {
+            return Err("email must be an email address".to_string());
+        }
+        Ok(())
+    }
+
+    /// The partial Keycloak `UserRepresentation` holding only the provided fields
+    pub fn representation(&self) -> Value {
+        let mut representation = Map::new();
+        if let Some(first_name) = &self.first_name {
+            representation.insert("firstName".to_string(), json!(first_name));
+        }
+        if let Some(last_name) = &self.last_name {
+            representation.insert("lastName".to_string(), json!(last_name));
+        }
+        if let Some(email) = &self.email {
+            representation.insert("email".to_string(), json!(email));
+        }
+        if let Some(enabled) = self.enabled {
+            representation.insert("enabled".to_string(), json!(enabled));
+        }
+        Value::Object(representation)
+    }
+}
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use futures_util::future::try_join_all;
+use std::time::{Duration, Instant};
+use tracing::info;
+
+use crate::jobs::Job;
+
+/// Background job logging pool utilization every `interval`, to help size `max_connections`
+pub struct PoolStatsReporter {
+    db: PgPool,
+    interval: Duration,
+}
+
+impl PoolStatsReporter {
+    pub fn new(db: PgPool, interval: Duration) -> Self {
+        Self { db, interval }
+    }
+}
+
+#[async_trait]
+impl Job for PoolStatsReporter {
+    fn name(&self) -> &'static str {
+        "pool_stats"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn tick(&mut self) {
+        log_pool_stats(&self.db, "Database pool stats");
+    }
+
+    async fn stop(&mut self) {
+        log_pool_stats(&self.db, "Final database pool stats");
+    }
+}
+
+/// Opens `connections` pool connections at once and returns them, so the first
+/// requests after a deploy don't pay for connection setup. Returns how long it took.
+pub async fn warm_up_pool(db: &PgPool, connections: u32) -> Result<Duration, sqlx::Error> {
+    let started = Instant::now();
+    let held = try_join_all((0..connections).map(|_| db.acquire())).await?;
+    drop(held);
+    Ok(started.elapsed())
+}
+
+fn log_pool_stats(db: &PgPool, message: &str) {
+    let size = db.size();
+    let idle = db.num_idle() as u32;
+    info!(
+        pool_size = size,
+        idle_connections = idle,
+        in_use_connections = size.saturating_sub(idle),
+        max_connections = db.options().get_max_connections(),
+        "{}", message
+    );
+}
@@ -0,0 +1,411 @@
+mod common;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+use task_api::handlers::{
+    admin::admin_user_tasks,
+    extract::IdPath,
+    ownership::{Tenant, DEFAULT_TENANT},
+    user::{
+        delete_user, disable_user, get_user, list_users, send_reset_password_email, send_verify_email,
+        update_user,
+    },
+};
+use task_api::keycloak::{CachedKeycloakClient, KeycloakClient, KeycloakUser, MockKeycloakClient};
+use task_api::models::{
+    response::Success,
+    query::{PageQuery, UserListQuery},
+    role::Role,
+    user::UpdateUserSchema,
+};
+use uuid::Uuid;
+
+const ALICE: &str = "5f3c1a9e-0d2b-4c8e-9a51-7b6d2e4f8a10";
+const BOB: &str = "a2e8d4c6-3f1b-4e7a-8c9d-0b5f6e2a1c34";
+
+fn mock_with_users() -> Arc<MockKeycloakClient> {
+    Arc::new(
+        MockKeycloakClient::new()
+            .with_user(
+                json!({"id": ALICE, "username": "alice", "email": "alice@example.com", "createdTimestamp": 1_700_000_000_000i64}),
+                Role::Admin,
+            )
+            .with_user(
+                json!({"id": BOB, "username": "bob", "email": "bob@example.com", "createdTimestamp": 1_700_000_000_000i64}),
+                Role::User,
+            ),
+    )
+}
+
+#[tokio::test]
+async fn list_users_maps_keycloak_users() {
+    let state = common::test_state(mock_with_users());
+
+    let Ok(Json(Success { data: users, .. })) = list_users(State(state), Query(UserListQuery { role: None })).await else {
+        panic!("list_users failed");
+    };
+
+    assert_eq!(users.len(), 2);
+    assert_eq!(users[0].id, Uuid::parse_str(ALICE).unwrap());
+    assert_eq!(users[0].name, "alice");
+    assert_eq!(users[1].email, "bob@example.com");
+    assert_eq!(users[0].role, "admin");
+    assert_eq!(users[1].role, "user");
+}
+
+#[tokio::test]
+async fn get_user_returns_one_user_with_their_role() {
+    let state = common::test_state(mock_with_users());
+    let alice = Uuid::parse_str(ALICE).unwrap();
+
+    let Ok(Json(Success { data: user, .. })) = get_user(State(state.clone()), IdPath(alice)).await else {
+        panic!("get_user failed");
+    };
+    assert_eq!(user.id, alice);
+    assert_eq!(user.name, "alice");
+    assert_eq!(user.role, "admin");
+
+    let Ok(Json(Success { data: user, .. })) = get_user(State(state), IdPath(Uuid::parse_str(BOB).unwrap())).await
+    else {
+        panic!("get_user failed");
+    };
+    assert_eq!(user.role, "user");
+    assert!(!user.role_resolution_error);
+}
+
+#[tokio::test]
+async fn get_user_falls_back_to_the_user_role_when_the_lookup_fails() {
+    let keycloak = mock_with_users();
+    keycloak.set_role_lookup_unavailable(true);
+    let state = common::test_state(keycloak);
+
+    let Ok(Json(Success { data: user, .. })) = get_user(State(state), IdPath(Uuid::parse_str(ALICE).unwrap())).await
+    else {
+        panic!("get_user failed");
+    };
+    assert_eq!(user.role, "user");
+    assert!(user.role_resolution_error);
+}
+
+#[tokio::test]
+async fn get_user_returns_404_for_unknown_users() {
+    let state = common::test_state(mock_with_users());
+
+    let Err(err) = get_user(State(state), IdPath(Uuid::new_v4())).await else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn update_user_only_changes_the_fields_sent() {
+    let keycloak = mock_with_users();
+    let state = common::test_state(keycloak.clone());
+    let bob = Uuid::parse_str(BOB).unwrap();
+
+    let payload = UpdateUserSchema { first_name: Some(" Robert ".to_string()), enabled: Some(false), ..Default::default() };
+    let Ok(_) = update_user(State(state), IdPath(bob), Json(payload)).await else {
+        panic!("update_user failed");
+    };
+
+    let user = keycloak.user(bob).unwrap();
+    assert_eq!(user["firstName"], "Robert");
+    assert_eq!(user["enabled"], false);
+    assert_eq!(user["email"], "bob@example.com");
+    assert!(user.get("lastName").is_none());
+}
+
+#[tokio::test]
+async fn update_user_maps_missing_users_and_email_conflicts() {
+    let state = common::test_state(mock_with_users());
+    let email = |email: &str| UpdateUserSchema { email: Some(email.to_string()), ..Default::default() };
+
+    let Err(err) = update_user(State(state.clone()), IdPath(Uuid::new_v4()), Json(email("new@example.com"))).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+    let bob = IdPath(Uuid::parse_str(BOB).unwrap());
+    let Err(err) = update_user(State(state.clone()), bob, Json(email("alice@example.com"))).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::CONFLICT);
+
+    let bob = IdPath(Uuid::parse_str(BOB).unwrap());
+    let Err(err) = update_user(State(state), bob, Json(UpdateUserSchema::default())).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn cached_user_listings_hit_keycloak_once_until_a_user_changes() {
+    let keycloak = mock_with_users();
+    let cached = Arc::new(CachedKeycloakClient::new(keycloak.clone(), Duration::from_secs(60)));
+    let state = common::test_state(cached);
+    let list = || list_users(State(state.clone()), Query(UserListQuery { role: None }));
+
+    let Ok(Json(Success { data: first, .. })) = list().await else {
+        panic!("list_users failed");
+    };
+    let after_first = keycloak.request_count();
+    let Ok(Json(Success { data: second, .. })) = list().await else {
+        panic!("list_users failed");
+    };
+    assert_eq!(keycloak.request_count(), after_first);
+    assert_eq!(second.len(), first.len());
+    assert_eq!(second[0].role, "admin");
+
+    let Ok(_) = disable_user(State(state.clone()), IdPath(Uuid::parse_str(BOB).unwrap())).await else {
+        panic!("disable_user failed");
+    };
+    let after_change = keycloak.request_count();
+    let Ok(_) = list().await else {
+        panic!("list_users failed");
+    };
+    assert!(keycloak.request_count() > after_change);
+}
+
+#[tokio::test]
+async fn listing_fetched_before_a_user_change_is_not_cached() {
+    let keycloak = mock_with_users();
+    let cached = Arc::new(CachedKeycloakClient::new(keycloak.clone(), Duration::from_secs(60)));
+    let bob = Uuid::parse_str(BOB).unwrap();
+    let gate = keycloak.hold_user_listings();
+
+    let in_flight = tokio::spawn({
+        let cached = cached.clone();
+        async move { cached.list_users(None).await }
+    });
+    while keycloak.request_count() == 0 {
+        tokio::task::yield_now().await;
+    }
+    let Ok(()) = cached.set_user_enabled(bob, false).await else {
+        panic!("set_user_enabled failed");
+    };
+    gate.notify_one();
+    let Ok(stale) = in_flight.await.unwrap() else {
+        panic!("list_users failed");
+    };
+    assert!(stale.iter().any(|user| user.id == bob && user.enabled != Some(false)));
+
+    let Ok(users) = cached.list_users(None).await else {
+        panic!("list_users failed");
+    };
+    assert!(users.iter().any(|user| user.id == bob && user.enabled == Some(false)));
+}
+
+#[tokio::test]
+async fn send_verify_email_asks_keycloak_for_known_users_only() {
+    let keycloak = Arc::new(
+        MockKeycloakClient::new()
+            .with_user(json!({"id": ALICE, "username": "alice", "email": "alice@example.com"}), Role::User)
+            .with_user(json!({"id": BOB, "username": "bob"}), Role::User),
+    );
+    let state = common::test_state(keycloak.clone());
+    let alice = Uuid::parse_str(ALICE).unwrap();
+
+    let Ok(response) = send_verify_email(State(state.clone()), IdPath(alice)).await else {
+        panic!("send_verify_email failed");
+    };
+    assert_eq!(
+        serde_json::to_value(&response.0).unwrap(),
+        json!({"status": "success", "data": {"message": format!("Verification email sent to user {}", alice)}})
+    );
+    assert_eq!(keycloak.sent_emails(), [(alice, "VERIFY_EMAIL".to_string())]);
+
+    let Err(err) = send_verify_email(State(state.clone()), IdPath(Uuid::new_v4())).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+
+    let Err(err) = send_verify_email(State(state), IdPath(Uuid::parse_str(BOB).unwrap())).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(keycloak.sent_emails().len(), 1);
+}
+
+#[tokio::test]
+async fn reset_password_email_requests_the_update_password_action() {
+    let keycloak = mock_with_users();
+    let state = common::test_state(keycloak.clone());
+    let bob = Uuid::parse_str(BOB).unwrap();
+
+    let Ok(_) = send_reset_password_email(State(state.clone()), IdPath(bob)).await else {
+        panic!("send_reset_password_email failed");
+    };
+    assert_eq!(keycloak.sent_emails(), [(bob, "UPDATE_PASSWORD".to_string())]);
+
+    let Err(err) = send_reset_password_email(State(state), IdPath(Uuid::new_v4())).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn keycloak_user_maps_missing_fields_to_defaults() {
+    let user: KeycloakUser = serde_json::from_value(json!({"id": ALICE})).unwrap();
+    let response = user.into_response(&Role::User);
+
+    assert_eq!(response.id, Uuid::parse_str(ALICE).unwrap());
+    assert_eq!(response.name, "unknown");
+    assert_eq!(response.email, "");
+    assert_eq!(response.role, "user");
+    assert!(response.created_at.is_none());
+    assert!(!response.verified);
+}
+
+#[test]
+fn keycloak_user_maps_email_verified() {
+    let unverified: KeycloakUser =
+        serde_json::from_value(json!({"id": ALICE, "emailVerified": false})).unwrap();
+    let verified: KeycloakUser = serde_json::from_value(json!({"id": BOB, "emailVerified": true})).unwrap();
+
+    assert!(!unverified.into_response(&Role::User).verified);
+    assert!(verified.into_response(&Role::User).verified);
+}
+
+#[tokio::test]
+async fn list_users_filters_by_role() {
+    let state = common::test_state(mock_with_users());
+
+    let query = UserListQuery { role: Some("admin".to_string()) };
+    let Ok(Json(Success { data: users, .. })) = list_users(State(state), Query(query)).await else {
+        panic!("list_users failed");
+    };
+
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].name, "alice");
+    assert_eq!(users[0].role, "admin");
+}
+
+#[tokio::test]
+async fn user_filter_means_everyone_but_admins() {
+    let keycloak = mock_with_users();
+    let state = common::test_state(keycloak.clone());
+
+    let query = UserListQuery { role: Some("user".to_string()) };
+    let Ok(Json(Success { data: users, .. })) = list_users(State(state.clone()), Query(query)).await else {
+        panic!("list_users failed");
+    };
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].name, "bob");
+    assert_eq!(users[0].role, "user");
+
+    // Without the admin members the filter can't be applied, so it fails rather than guess
+    keycloak.set_role_lookup_unavailable(true);
+    let query = UserListQuery { role: Some("user".to_string()) };
+    let Err(err) = list_users(State(state), Query(query)).await else {
+        panic!("expected an error");
+    };
+    assert_eq!(err.into_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn list_users_rejects_unknown_role_without_calling_keycloak() {
+    let keycloak = mock_with_users();
+    let state = common::test_state(keycloak.clone());
+
+    let query = UserListQuery { role: Some("superuser".to_string()) };
+    let Err(err) = list_users(State(state), Query(query)).await else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+    assert_eq!(keycloak.request_count(), 0);
+}
+
+#[tokio::test]
+async fn list_users_surfaces_keycloak_errors() {
+    let keycloak = mock_with_users();
+    keycloak.set_unavailable(true);
+    let state = common::test_state(keycloak);
+
+    let Err(err) = list_users(State(state), Query(UserListQuery { role: None })).await else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(err.into_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn list_users_survives_a_failed_role_lookup() {
+    let keycloak = mock_with_users();
+    keycloak.set_role_lookup_unavailable(true);
+    let state = common::test_state(keycloak);
+
+    let Ok(Json(Success { data: users, .. })) = list_users(State(state), Query(UserListQuery { role: None })).await else {
+        panic!("list_users failed");
+    };
+
+    assert_eq!(users.len(), 2);
+    assert!(users.iter().all(|u| u.role == "user" && u.role_resolution_error));
+    let json = serde_json::to_value(&users[0]).unwrap();
+    assert_eq!(json["role"], "user");
+    assert_eq!(json["role_resolution_error"], true);
+}
+
+#[tokio::test]
+async fn resolved_roles_omit_the_error_flag() {
+    let state = common::test_state(mock_with_users());
+
+    let Ok(Json(Success { data: users, .. })) = list_users(State(state), Query(UserListQuery { role: None })).await else {
+        panic!("list_users failed");
+    };
+
+    let json = serde_json::to_value(&users[0]).unwrap();
+    assert!(json.get("role_resolution_error").is_none());
+}
+
+#[tokio::test]
+async fn delete_user_returns_404_for_unknown_user() {
+    let keycloak = mock_with_users();
+    let state = common::test_state(keycloak.clone());
+
+    let Err(err) = delete_user(State(state), IdPath(Uuid::new_v4())).await else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+    assert!(keycloak.user(Uuid::parse_str(ALICE).unwrap()).is_some());
+}
+
+#[tokio::test]
+async fn delete_user_surfaces_keycloak_errors() {
+    let keycloak = mock_with_users();
+    keycloak.set_unavailable(true);
+    let state = common::test_state(keycloak.clone());
+
+    let Err(err) = delete_user(State(state), IdPath(Uuid::parse_str(BOB).unwrap())).await else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(err.into_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(keycloak.user(Uuid::parse_str(BOB).unwrap()).is_some());
+}
+
+#[tokio::test]
+async fn admin_user_tasks_returns_404_for_unknown_user() {
+    let state = common::test_state(mock_with_users());
+
+    let Err(err) = admin_user_tasks(
+        Tenant(DEFAULT_TENANT.to_string()),
+        State(state),
+        IdPath(Uuid::new_v4()),
+        Query(PageQuery { page: None, per_page: None }),
+    )
+    .await
+    else {
+        panic!("expected an error");
+    };
+
+    assert_eq!(err.into_response().status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,40 @@
+use chrono::Utc;
+use serde_json::json;
+use task_api::models::response::{TaskFields, TaskResponse};
+use uuid::Uuid;
+
+fn task() -> TaskResponse {
+    TaskResponse {
+        id: Uuid::nil(),
+        name: "Write docs".to_string(),
+        user_id: Uuid::nil(),
+        description: Some("for the API".to_string()),
+        position: 1,
+        client_id: Some("local-1".to_string()),
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn selects_only_requested_fields() {
+    let fields = TaskFields::parse("id, name").unwrap();
+
+    assert_eq!(fields.select(task()), json!({"id": Uuid::nil(), "name": "Write docs"}));
+}
+
+#[test]
+fn every_known_field_is_selectable() {
+    let fields = TaskFields::parse(&TaskFields::ALL.join(",")).unwrap();
+    let full = serde_json::to_value(task()).unwrap();
+
+    assert_eq!(fields.select(task()).as_object().unwrap().len(), full.as_object().unwrap().len());
+}
+
+#[test]
+fn rejects_unknown_and_empty_field_lists() {
+    let err = TaskFields::parse("id,secret").unwrap_err();
+    assert!(err.contains("Unknown field 'secret'"), "{}", err);
+
+    assert!(TaskFields::parse(" , ").is_err());
+}
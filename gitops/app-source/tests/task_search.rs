@@ -0,0 +1,41 @@
+use task_api::models::{query::page_bounds, task::TaskSearchSchema};
+use task_api::queries::TaskSort;
+
+fn search(body: &str) -> Result<TaskSearchSchema, serde_json::Error> {
+    serde_json::from_str(body)
+}
+
+#[test]
+fn sort_maps_to_fixed_orderings() {
+    assert_eq!(search("{}").unwrap().sort(), Ok(TaskSort::CreatedAt));
+    assert_eq!(search(r#"{"sort": "name"}"#).unwrap().sort(), Ok(TaskSort::Name));
+    assert!(search(r#"{"sort": "name; DROP TABLE tasks"}"#).unwrap().sort().is_err());
+}
+
+#[test]
+fn text_is_matched_literally() {
+    assert_eq!(search(r#"{"text": " 50%_off "}"#).unwrap().text_pattern().as_deref(), Some(r"%50\%\_off%"));
+    assert_eq!(search(r#"{"text": "   "}"#).unwrap().text_pattern(), None);
+}
+
+#[test]
+fn unsupported_criteria_are_rejected() {
+    assert!(search(r#"{"archived": true}"#).is_err());
+    assert!(search(r#"{"created_range": {"from": "2026-01-01T00:00:00Z", "until": "x"}}"#).is_err());
+    let range = search(r#"{"created_range": {"from": "2026-01-01T00:00:00Z"}}"#).unwrap();
+    assert!(range.created_range.unwrap().to.is_none());
+}
+
+#[test]
+fn status_priority_and_tags_are_named_as_unsupported() {
+    assert_eq!(search(r#"{"status": "done"}"#).unwrap().unsupported_filter(), Some("status"));
+    assert_eq!(search(r#"{"text": "x", "priority": 1}"#).unwrap().unsupported_filter(), Some("priority"));
+    assert_eq!(search(r#"{"tags": ["home"]}"#).unwrap().unsupported_filter(), Some("tags"));
+    assert_eq!(search(r#"{"text": "x"}"#).unwrap().unsupported_filter(), None);
+}
+
+#[test]
+fn pages_default_and_are_capped() {
+    assert_eq!(page_bounds(None, None), (1, 20));
+    assert_eq!(page_bounds(Some(0), Some(1_000)), (1, 100));
+}
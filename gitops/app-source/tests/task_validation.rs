@@ -0,0 +1,93 @@
+use task_api::models::task::{CreateTaskSchema, DescriptionNameRule, ReorderTasksSchema};
+use uuid::Uuid;
+
+fn task(name: &str, description: Option<&str>) -> CreateTaskSchema {
+    CreateTaskSchema {
+        name: name.to_string(),
+        description: description.map(String::from),
+        client_id: None,
+    }
+}
+
+#[test]
+fn name_is_trimmed_and_must_not_be_blank() {
+    let mut trimmed = task("  Write docs \n", None);
+    assert_eq!(trimmed.validate(255, 10_000), Ok(()));
+    assert_eq!(trimmed.name, "Write docs");
+
+    assert_eq!(task("   ", None).validate(255, 10_000), Err("name is required".to_string()));
+}
+
+#[test]
+fn name_limit_is_inclusive() {
+    assert_eq!(task(&"a".repeat(255), None).validate(255, 10_000), Ok(()));
+    // Counted in characters, not bytes
+    assert_eq!(task(&"é".repeat(255), None).validate(255, 10_000), Ok(()));
+    assert_eq!(
+        task(&"a".repeat(256), None).validate(255, 10_000),
+        Err("name must be at most 255 characters".to_string())
+    );
+    // Surrounding whitespace doesn't count towards the limit
+    assert_eq!(task(&format!(" {} ", "a".repeat(255)), None).validate(255, 10_000), Ok(()));
+}
+
+#[test]
+fn description_limit_is_inclusive() {
+    let at_limit = "d".repeat(10_000);
+    let over_limit = "d".repeat(10_001);
+
+    assert_eq!(task("ok", Some(&at_limit)).validate(255, 10_000), Ok(()));
+    assert_eq!(
+        task("ok", Some(&over_limit)).validate(255, 10_000),
+        Err("description must be at most 10000 characters".to_string())
+    );
+}
+
+#[test]
+fn description_equal_to_name_is_detected_after_trimming() {
+    let mut same = task(" Write docs ", Some("Write docs "));
+    same.validate(255, 10_000).unwrap();
+    assert!(same.description_equals_name());
+
+    assert!(!task("Write docs", Some("Write the docs")).description_equals_name());
+    assert!(!task("Write docs", None).description_equals_name());
+}
+
+#[test]
+fn description_name_rule_parses_known_modes() {
+    assert_eq!("off".parse(), Ok(DescriptionNameRule::Off));
+    assert_eq!("Warn".parse(), Ok(DescriptionNameRule::Warn));
+    assert_eq!("reject".parse(), Ok(DescriptionNameRule::Reject));
+    assert!("block".parse::<DescriptionNameRule>().is_err());
+    assert_eq!(DescriptionNameRule::default(), DescriptionNameRule::Off);
+}
+
+#[test]
+fn reorder_ids_are_capped_and_unique() {
+    let ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+
+    assert_eq!(ReorderTasksSchema { task_ids: ids.clone() }.validate(3), Ok(()));
+    assert_eq!(
+        ReorderTasksSchema { task_ids: ids.clone() }.validate(2),
+        Err("task_ids must contain at most 2 ids".to_string())
+    );
+    assert_eq!(
+        ReorderTasksSchema { task_ids: vec![ids[0], ids[1], ids[0]] }.validate(3),
+        Err("task_ids must not contain duplicates".to_string())
+    );
+}
+
+#[test]
+fn client_id_is_trimmed_and_length_checked() {
+    let mut with_id = task("Write docs", None);
+    with_id.client_id = Some(" local-1 ".to_string());
+    assert_eq!(with_id.validate(255, 10_000), Ok(()));
+    assert_eq!(with_id.client_id.as_deref(), Some("local-1"));
+
+    with_id.client_id = Some("a".repeat(64));
+    assert_eq!(with_id.validate(255, 10_000), Ok(()));
+    with_id.client_id = Some("a".repeat(65));
+    assert_eq!(with_id.validate(255, 10_000), Err("client_id must be at most 64 characters".to_string()));
+    with_id.client_id = Some("  ".to_string());
+    assert_eq!(with_id.validate(255, 10_000), Err("client_id must not be blank".to_string()));
+}
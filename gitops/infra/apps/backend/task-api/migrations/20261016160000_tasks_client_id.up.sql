@@ -0,0 +1,2 @@
+-- Id an offline client generated for its optimistic copy of the task, echoed back so it can reconcile
+ALTER TABLE "tasks" ADD COLUMN IF NOT EXISTS "client_id" VARCHAR(64);
@@ -0,0 +1,6 @@
+-- Runtime switches every replica reads, such as maintenance mode
+CREATE TABLE IF NOT EXISTS "settings" (
+    "key" TEXT PRIMARY KEY,
+    "value" TEXT NOT NULL,
+    "updated_at" TIMESTAMPTZ NOT NULL DEFAULT NOW ()
+);
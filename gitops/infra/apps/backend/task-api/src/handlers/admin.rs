@@ -0,0 +1,310 @@
+use crate::handlers::{
+    extract::IdPath,
+    ownership::{current_user_id, Tenant},
+};
+use crate::models::{
+    error::AppError,
+    query::{page_bounds, AdminTaskListQuery, PageQuery},
+    response::{deleted, success, AdminStats, MaintenanceStatus, TaskPage, Success, TaskResponse},
+    role::Role,
+    state::AppState,
+    task::Task,
+};
+use crate::{maintenance, queries};
+use axum::{
+    extract::{Extension, Query, State},
+    http::StatusCode,
+    response::Response,
+    Json,
+};
+use axum_keycloak_auth::decode::KeycloakToken;
+use serde_json::json;
+use std::sync::{atomic::Ordering, Arc};
+use tracing::{info, debug, warn};
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/stats",
+    tag = "users",
+    responses(
+        (status = 200, description = "Realm-wide user counts and the task count of the admin's tenant", body = Success<AdminStats>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn admin_stats(
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Success<AdminStats>>, AppError> {
+    debug!("Collecting admin statistics");
+
+    let total_users = state.keycloak.count_users(None).await?;
+    let verified_users = state.keycloak.count_users(Some(true)).await?;
+
+    // Like the admin task list, only the admin's own tenant; no user filter counts every owner
+    let total_tasks: i64 = sqlx::query_scalar(queries::ADMIN_TASK_COUNT)
+        .bind(&tenant.0)
+        .bind(None::<uuid::Uuid>)
+        .fetch_one(&state.db_read)
+        .await
+        .map_err(|e| AppError::db(&state.db_read, e, "Failed to count tasks"))?;
+
+    let stats = AdminStats {
+        total_users,
+        verified_users,
+        unverified_users: total_users - verified_users,
+        total_tasks,
+    };
+
+    info!(
+        total_users = stats.total_users,
+        verified_users = stats.verified_users,
+        tenant_id = %tenant.0,
+        total_tasks = stats.total_tasks,
+        "Admin statistics collected"
+    );
+
+    Ok(success(stats))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/tasks",
+    tag = "users",
+    params(AdminTaskListQuery),
+    responses(
+        (status = 200, description = "One page of tasks across all users in the admin's tenant", body = Success<TaskPage>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn admin_list_tasks(
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AdminTaskListQuery>,
+) -> Result<Json<Success<TaskPage>>, AppError> {
+    let page = fetch_task_page(&state, &tenant, query.user_id, query.page, query.per_page).await?;
+
+    Ok(success(page))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/users/{id}/tasks",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID"),
+        PageQuery
+    ),
+    responses(
+        (status = 200, description = "One page of the user's tasks in the admin's tenant", body = Success<TaskPage>),
+        (status = 400, description = "Malformed user ID"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn admin_user_tasks(
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    IdPath(user_id): IdPath,
+    Query(query): Query<PageQuery>,
+) -> Result<Json<Success<TaskPage>>, AppError> {
+    // An unknown user is a 404 rather than an empty page, so typos in the id aren't silent
+    state.keycloak.get_user(user_id).await?;
+
+    let page = fetch_task_page(&state, &tenant, Some(user_id), query.page, query.per_page).await?;
+
+    Ok(success(page))
+}
+
+// Newest-first page of the tenant's tasks, optionally limited to one owner
+async fn fetch_task_page(
+    state: &AppState,
+    tenant: &Tenant,
+    user_id: Option<uuid::Uuid>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+) -> Result<TaskPage, AppError> {
+    let (page, per_page) = page_bounds(page, per_page);
+
+    debug!(
+        tenant_id = %tenant.0,
+        target_user_id = ?user_id,
+        page,
+        per_page,
+        "Listing tasks for admin"
+    );
+
+    let total: i64 = sqlx::query_scalar(queries::ADMIN_TASK_COUNT)
+    .bind(&tenant.0)
+    .bind(user_id)
+    .fetch_one(&state.db_read)
+    .await
+    .map_err(|e| AppError::db(&state.db_read, e, "Failed to fetch tasks"))?;
+
+    let tasks = sqlx::query_as::<_, Task>(queries::ADMIN_TASK_PAGE)
+    .bind(&tenant.0)
+    .bind(user_id)
+    .bind(per_page)
+    .bind((page - 1).saturating_mul(per_page))
+    .fetch_all(&state.db_read)
+    .await
+    .map_err(|e| AppError::db(&state.db_read, e, "Failed to fetch tasks"))?;
+
+    info!(
+        tenant_id = %tenant.0,
+        target_user_id = ?user_id,
+        page,
+        task_count = tasks.len(),
+        total,
+        "Admin task list retrieved"
+    );
+
+    Ok(TaskPage {
+        tasks: tasks.into_iter().map(TaskResponse::from).collect(),
+        page,
+        per_page,
+        total,
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/tasks/{id}",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "Task ID")
+    ),
+    responses(
+        (status = 204, description = "Task deleted"),
+        (status = 200, description = "Task deleted, instead of 204 when DELETE_RETURNS_BODY=true", body = serde_json::Value, example = json!({"status": "success"})),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Task not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn admin_delete_task(
+    Extension(token): Extension<KeycloakToken<Role>>,
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+) -> Result<Response, AppError> {
+    let admin_id = current_user_id(&token)?;
+
+    debug!(
+        task_id = %id,
+        "Admin attempting to delete task"
+    );
+
+    // Any owner, but only within the admin's own tenant
+    let owner_id: Option<uuid::Uuid> = sqlx::query_scalar(queries::ADMIN_DELETE_TASK)
+    .bind(id)
+    .bind(&tenant.0)
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| AppError::db(&state.db, e, "Failed to delete task"))?;
+
+    let Some(owner_id) = owner_id else {
+        warn!(task_id = %id, "Task not found for admin delete");
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({"status": "fail", "error": "Task not found"})),
+        )
+            .into());
+    };
+
+    info!(
+        target: "audit",
+        action = "admin_delete_task",
+        actor_id = %admin_id,
+        owner_id = %owner_id,
+        task_id = %id,
+        tenant_id = %tenant.0,
+        "Admin deleted a task"
+    );
+
+    Ok(deleted(state.config.delete_returns_body))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/admin/maintenance",
+    tag = "users",
+    description = "This pod's view, refreshed from the database every `MAINTENANCE_POLL_SECS`",
+    responses(
+        (status = 200, description = "Whether maintenance mode is on", body = Success<MaintenanceStatus>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn maintenance_status(State(state): State<Arc<AppState>>) -> Json<Success<MaintenanceStatus>> {
+    success(MaintenanceStatus {
+        enabled: state.maintenance.load(Ordering::Relaxed),
+    })
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/admin/maintenance",
+    tag = "users",
+    description = "Stored in the database: this pod switches at once, other replicas within `MAINTENANCE_POLL_SECS`",
+    request_body = MaintenanceStatus,
+    responses(
+        (status = 200, description = "Maintenance mode after the change", body = Success<MaintenanceStatus>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 422, description = "Missing or malformed `enabled`"),
+        (status = 500, description = "Failed to store the switch")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn set_maintenance(
+    Extension(token): Extension<KeycloakToken<Role>>,
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<MaintenanceStatus>,
+) -> Result<Json<Success<MaintenanceStatus>>, AppError> {
+    let admin_id = current_user_id(&token)?;
+
+    maintenance::store(&state.db, payload.enabled)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to store maintenance mode"))?;
+    let previous = state.maintenance.swap(payload.enabled, Ordering::Relaxed);
+    if previous != payload.enabled {
+        warn!(
+            target: "audit",
+            action = "set_maintenance",
+            actor_id = %admin_id,
+            enabled = payload.enabled,
+            "Maintenance mode {}",
+            if payload.enabled { "enabled" } else { "disabled" }
+        );
+    }
+
+    Ok(success(payload))
+}
@@ -3,20 +3,55 @@ use axum::{
     middleware::Next,
     response::Response,
 };
+use axum_keycloak_auth::decode::KeycloakToken;
+use crate::models::{
+    client_ip::ClientIp,
+    logging::{is_health_check, log_health_checks, quiet_paths, sampler, slow_request_threshold},
+    role::Role,
+};
 use std::time::Instant;
-use tracing::{info, warn, error, debug};
+use tracing::{info, warn, error, debug, trace, error_span, Instrument};
 use uuid::Uuid;
 
+/// Runs the rest of the request inside a `user` span carrying the caller's `user_id` and
+/// `role`, so handler log lines are attributed without repeating the fields. Sits inside the
+/// auth layers; a request without a decoded token passes through unchanged.
+pub async fn user_span(request: Request, next: Next) -> Response {
+    let Some(token) = request.extensions().get::<KeycloakToken<Role>>() else {
+        return next.run(request).await;
+    };
+    let role = if token.roles.iter().any(|r| *r.role() == Role::Admin) {
+        Role::Admin
+    } else {
+        Role::User
+    };
+
+    // ERROR level keeps the span enabled under any LOG_LEVEL, so even warnings carry the user
+    let span = error_span!("user", user_id = %token.subject, role = %role);
+    next.run(request).instrument(span).await
+}
+
+/// The id `logging_middleware` gives a request, stored as an extension so later
+/// middleware can tie its log lines to the request's
+#[derive(Debug, Clone, Copy)]
+pub struct RequestId(pub Uuid);
+
 pub async fn logging_middleware(
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
     let request_id = Uuid::new_v4();
+    request.extensions_mut().insert(RequestId(request_id));
     let start = Instant::now();
     
     let method = request.method().clone();
     let uri = request.uri().clone();
     let version = request.version();
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map(ClientIp::to_string)
+        .unwrap_or_else(|| "unknown".to_string());
     
     // Extract matched path if available (for route-based logging)
     let path = request
@@ -40,20 +75,84 @@ pub async fn logging_middleware(
         })
         .collect();
 
-    info!(
-        request_id = %request_id,
-        method = %method,
-        uri = %uri,
-        path = path,
-        version = ?version,
-        headers = ?headers,
-        "HTTP request started"
-    );
+    // Probes are logged at trace unless LOG_HEALTH_CHECKS=true or they fail with a 5xx
+    let probe = !log_health_checks() && is_health_check(&path);
+
+    if probe {
+        trace!(
+            request_id = %request_id,
+            method = %method,
+            uri = %uri,
+            path = path,
+            client_ip = %client_ip,
+            version = ?version,
+            headers = ?headers,
+            "HTTP request started"
+        );
+    } else {
+        debug!(
+            request_id = %request_id,
+            method = %method,
+            uri = %uri,
+            path = path,
+            client_ip = %client_ip,
+            version = ?version,
+            headers = ?headers,
+            "HTTP request started"
+        );
+    }
 
     let response = next.run(request).await;
     let duration = start.elapsed();
     let status = response.status();
 
+    if let Some(threshold) = slow_request_threshold() {
+        if duration > threshold {
+            warn!(
+                request_id = %request_id,
+                method = %method,
+                path = path,
+                client_ip = %client_ip,
+                status = %status,
+                duration_ms = duration.as_millis(),
+                threshold_ms = threshold.as_millis(),
+                "HTTP request exceeded slow request threshold"
+            );
+        }
+    }
+
+    if probe && !status.is_server_error() {
+        trace!(
+            request_id = %request_id,
+            method = %method,
+            uri = %uri,
+            path = path,
+            client_ip = %client_ip,
+            status = %status,
+            duration_ms = duration.as_millis(),
+            "HTTP request completed"
+        );
+        return response;
+    }
+
+    if quiet_paths().is_quiet(&path, status) {
+        debug!(
+            request_id = %request_id,
+            method = %method,
+            uri = %uri,
+            path = path,
+            client_ip = %client_ip,
+            status = %status,
+            duration_ms = duration.as_millis(),
+            "HTTP request completed"
+        );
+        return response;
+    }
+
+    if !sampler().should_log(status) {
+        return response;
+    }
+
     // Log based on status code level
     match status.as_u16() {
         200..=299 => {
@@ -62,6 +161,7 @@ pub async fn logging_middleware(
                 method = %method,
                 uri = %uri,
                 path = path,
+                client_ip = %client_ip,
                 status = %status,
                 duration_ms = duration.as_millis(),
                 "HTTP request completed successfully"
@@ -73,6 +173,7 @@ pub async fn logging_middleware(
                 method = %method,
                 uri = %uri,
                 path = path,
+                client_ip = %client_ip,
                 status = %status,
                 duration_ms = duration.as_millis(),
                 "HTTP request redirected"
@@ -84,6 +185,7 @@ pub async fn logging_middleware(
                 method = %method,
                 uri = %uri,
                 path = path,
+                client_ip = %client_ip,
                 status = %status,
                 duration_ms = duration.as_millis(),
                 "HTTP request client error"
@@ -95,6 +197,7 @@ pub async fn logging_middleware(
                 method = %method,
                 uri = %uri,
                 path = path,
+                client_ip = %client_ip,
                 status = %status,
                 duration_ms = duration.as_millis(),
                 "HTTP request server error"
@@ -106,6 +209,7 @@ pub async fn logging_middleware(
                 method = %method,
                 uri = %uri,
                 path = path,
+                client_ip = %client_ip,
                 status = %status,
                 duration_ms = duration.as_millis(),
                 "HTTP request completed"
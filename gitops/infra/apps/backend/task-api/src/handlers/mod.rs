@@ -1,5 +1,16 @@
+pub mod admin;
 pub mod user;
+pub mod session;
 pub mod task;
+pub mod export;
+pub mod import;
 pub mod middleware;
+pub mod transaction;
+pub mod ownership;
 pub mod health;
+pub mod extract;
+pub mod fallback;
+pub mod dev_auth;
 pub mod logging_middleware;
+#[cfg(feature = "test-auth")]
+pub mod test_auth;
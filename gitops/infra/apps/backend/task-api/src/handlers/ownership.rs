@@ -0,0 +1,119 @@
+use crate::models::{error::AppError, role::Role, state::AppState, task::Task};
+use crate::queries;
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use axum_keycloak_auth::decode::{KeycloakToken, RawClaims};
+use serde_json::json;
+use sqlx::PgPool;
+use std::sync::Arc;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+/// Tenant for tokens without the `TENANT_CLAIM` claim, and for tasks created before tenancy
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Longest accepted tenant, matching the `tenant_id` column
+pub const TENANT_MAX_CHARS: usize = 100;
+
+/// The caller's tenant, read from the `TENANT_CLAIM` claim of the decoded token.
+/// Every task query filters on it alongside the user id. A claim that is present but not a
+/// non-empty string of at most [`TENANT_MAX_CHARS`] characters is rejected with 403.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant(pub String);
+
+impl FromRequestParts<Arc<AppState>> for Tenant {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let Some(claim) = parts
+            .extensions
+            .get::<RawClaims>()
+            .and_then(|claims| claims.get(&state.config.tenant_claim))
+        else {
+            return Ok(Tenant(DEFAULT_TENANT.to_string()));
+        };
+
+        match claim.as_str().map(str::trim) {
+            Some(tenant) if !tenant.is_empty() && tenant.chars().count() <= TENANT_MAX_CHARS => {
+                Ok(Tenant(tenant.to_string()))
+            }
+            _ => {
+                warn!(claim = %state.config.tenant_claim, "Rejected token with an invalid tenant claim");
+                Err((
+                    StatusCode::FORBIDDEN,
+                    Json(json!({
+                        "status": "fail",
+                        "error": "Invalid tenant claim"
+                    })),
+                )
+                    .into())
+            }
+        }
+    }
+}
+
+/// The caller's user id, taken from the token subject
+pub fn current_user_id(token: &KeycloakToken<Role>) -> Result<Uuid, AppError> {
+    Uuid::parse_str(&token.subject).map_err(|e| {
+        error!(
+            user_id_str = %token.subject,
+            error = %e,
+            "Failed to parse user_id as UUID"
+        );
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "status": "fail",
+                "error": "Invalid user ID format"
+            })),
+        )
+            .into()
+    })
+}
+
+/// Loads a task owned by `user_id` in `tenant`; tasks of other users or tenants are reported as not found.
+/// Only for reads: a handler that writes must scope the write itself, as [`delete_owned_task`] does,
+/// since the task can change hands or disappear between this lookup and a separate statement.
+pub async fn fetch_owned_task(db: &PgPool, task_id: Uuid, user_id: Uuid, tenant: &Tenant) -> Result<Task, AppError> {
+    let task = sqlx::query_as::<_, Task>(queries::OWNED_TASK)
+        .bind(task_id)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| AppError::db(db, e, "Failed to fetch task"))?;
+    task.ok_or_else(|| task_not_found(task_id, tenant))
+}
+
+/// Deletes a task owned by `user_id` in `tenant` in one statement, so nothing can change
+/// between the ownership check and the write. Tasks of other users or tenants are reported as not found.
+pub async fn delete_owned_task(db: &PgPool, task_id: Uuid, user_id: Uuid, tenant: &Tenant) -> Result<(), AppError> {
+    let deleted: Option<Uuid> = sqlx::query_scalar(queries::DELETE_OWNED_TASK)
+        .bind(task_id)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| AppError::db(db, e, "Failed to delete task"))?;
+
+    deleted.map(|_| ()).ok_or_else(|| task_not_found(task_id, tenant))
+}
+
+fn task_not_found(task_id: Uuid, tenant: &Tenant) -> AppError {
+    warn!(
+        tenant_id = %tenant.0,
+        task_id = %task_id,
+        "Task not found for user"
+    );
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "status": "fail",
+            "error": "Task not found"
+        })),
+    )
+        .into()
+}
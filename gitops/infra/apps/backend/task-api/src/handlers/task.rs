@@ -1,17 +1,26 @@
+use crate::handlers::{
+    extract::IdPath,
+    ownership::{current_user_id, delete_owned_task, Tenant},
+    transaction::Tx,
+};
 use crate::models::{
-    response::{TaskListResponse, TaskResponse},
+    error::AppError,
+    query::{page_bounds, RecentTasksQuery, TaskListQuery},
+    response::{deleted, success, Success, TaskFields, TaskListResponse, TaskPage, TaskResponse},
     state::AppState,
-    task::{CreateTaskSchema, Task},
+    task::{CreateTaskSchema, DescriptionNameRule, ReorderTasksSchema, Task, TaskSearchSchema},
     role::Role,
 };
 use axum::{
-    extract::{Extension, Path, State},
+    extract::{Extension, Query, State},
     http::StatusCode,
+    response::Response,
     Json,
 };
+use crate::queries;
 use serde_json::json;
 use std::sync::Arc;
-use tracing::{info, warn, error, debug};
+use tracing::{info, warn, debug};
 
 #[utoipa::path(
     post,
@@ -19,10 +28,12 @@ use tracing::{info, warn, error, debug};
     tag = "tasks",
     request_body = CreateTaskSchema,
     responses(
-        (status = 201, description = "Task created successfully", body = TaskResponse),
+        (status = 201, description = "Task created successfully", body = Success<TaskResponse>),
         (status = 400, description = "Invalid input"),
         (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Internal server error")
+        (status = 409, description = "Task limit reached or duplicate task"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
     ),
     security(
         ("api_jwt_token" = [])
@@ -31,86 +42,142 @@ use tracing::{info, warn, error, debug};
 #[axum::debug_handler]
 pub async fn create_task(
     Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
     State(state): State<Arc<AppState>>,
-    Json(payload): Json<CreateTaskSchema>,
-) -> Result<(StatusCode, Json<serde_json::Value>), (StatusCode, Json<serde_json::Value>)> {
-    let user_id_str = &token.subject;
-    
-    // Parse user_id string to UUID
-    let user_id = uuid::Uuid::parse_str(user_id_str).map_err(|e| {
-        error!(
-            user_id_str = %user_id_str,
-            error = %e,
-            "Failed to parse user_id as UUID"
-        );
-        (
+    mut tx: Tx,
+    Json(mut payload): Json<CreateTaskSchema>,
+) -> Result<(StatusCode, Json<Success<TaskResponse>>), AppError> {
+    let user_id = current_user_id(&token)?;
+
+    if let Err(e) = payload.validate(state.config.task_name_max_chars, state.config.task_description_max_chars) {
+        warn!(error = %e, "Rejected invalid task");
+        return Err((
             StatusCode::BAD_REQUEST,
             Json(json!({
                 "status": "fail",
-                "error": "Invalid user ID format"
+                "error": e
             })),
         )
-    })?;
+            .into());
+    }
     
+    let rule = state.config.description_equals_name;
+    if rule != DescriptionNameRule::Off && payload.description_equals_name() {
+        debug!(rule = ?rule, "Task description is identical to its name");
+        if rule == DescriptionNameRule::Reject {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "fail",
+                    "error": "description must differ from name"
+                })),
+            )
+                .into());
+        }
+    }
+
     debug!(
-        user_id = %user_id,
         task_name = %payload.name,
         "Creating new task"
     );
 
-    let task = sqlx::query_as::<_, Task>(
-        r#"
-        INSERT INTO tasks (name, description, user_id, created_at, updated_at)
-        VALUES ($1, $2, $3, NOW(), NOW())
-        RETURNING *
-        "#,
-    )
-    .bind(&payload.name)
-    .bind(&payload.description)
-    .bind(user_id)
-    .fetch_one(&state.db)
-    .await
-    .map_err(|e| {
-        error!(
-            user_id = %user_id,
-            task_name = %payload.name,
-            error = %e,
-            "Failed to create task in database"
-        );
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "status": "fail",
-                "error": "Failed to create task",
-                "details": e.to_string()
-            })),
-        )
-    })?;
+    // Held until the transaction middleware commits, so concurrent creates can't both pass the
+    // limit or duplicate check
+    sqlx::query(queries::LOCK_USER_TASKS)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .execute(tx.conn().await?)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to create task"))?;
+
+    if let Some(max_tasks) = state.config.max_tasks_per_user {
+        // Served from idx_tasks_user_id, so this stays cheap even for large tables
+        let task_count: i64 = sqlx::query_scalar(queries::USER_TASK_COUNT)
+            .bind(user_id)
+            .bind(&tenant.0)
+            .fetch_one(tx.conn().await?)
+            .await
+            .map_err(|e| AppError::db(&state.db, e, "Failed to create task"))?;
 
+        if task_count >= max_tasks {
+            warn!(
+                task_count,
+                max_tasks,
+                "Task limit reached"
+            );
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "status": "fail",
+                    "error": "task limit reached"
+                })),
+            )
+                .into());
+        }
+    }
+
+    if let Some(window_minutes) = state.config.duplicate_window_minutes {
+        let is_duplicate: bool = sqlx::query_scalar(queries::RECENT_DUPLICATE_EXISTS)
+            .bind(user_id)
+            .bind(&tenant.0)
+            .bind(&payload.name)
+            .bind(window_minutes)
+            .fetch_one(tx.conn().await?)
+            .await
+            .map_err(|e| AppError::db(&state.db, e, "Failed to create task"))?;
+
+        if is_duplicate {
+            warn!(
+                task_name = %payload.name,
+                window_minutes,
+                "Duplicate task rejected"
+            );
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "status": "fail",
+                    "error": "Duplicate task",
+                    "details": format!(
+                        "A task with this name was created in the last {} minutes",
+                        window_minutes
+                    )
+                })),
+            )
+                .into());
+        }
+    }
+
+    let task = sqlx::query_as::<_, Task>(queries::INSERT_TASK)
+        .bind(&payload.name)
+        .bind(&payload.description)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .bind(&payload.client_id)
+        .fetch_one(tx.conn().await?)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to create task"))?;
+
+    // Committed by the transaction middleware once this returns 2xx
     info!(
-        user_id = %user_id,
         task_id = %task.id,
         task_name = %task.name,
         "Task created successfully"
     );
 
-    Ok((
-        StatusCode::CREATED,
-        Json(json!({
-            "status": "success",
-            "data": TaskResponse::from(task)
-        })),
-    ))
+    Ok((StatusCode::CREATED, success(TaskResponse::from(task))))
 }
 
 #[utoipa::path(
     get,
     path = "/api/tasks",
     tag = "tasks",
+    params(TaskListQuery),
     responses(
-        (status = 200, description = "List of tasks", body = TaskListResponse),
+        (status = 200, description = "List of tasks; only the requested task fields with `?fields`", body = Success<TaskListResponse>),
+        (status = 400, description = "Unsupported sort or unknown field"),
         (status = 401, description = "Unauthorized"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
     ),
     security(
         ("api_jwt_token" = [])
@@ -118,63 +185,216 @@ pub async fn create_task(
 )]
 pub async fn list_tasks(
     Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
-    let user_id_str = &token.subject;
+    Query(query): Query<TaskListQuery>,
+) -> Result<Json<Success<serde_json::Value>>, AppError> {
+    let user_id = current_user_id(&token)?;
+
+    let sql = match queries::list_tasks(query.sort.as_deref()) {
+        Some(sql) => sql,
+        None => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "fail",
+                    "error": format!(
+                        "Unsupported sort '{}', expected position or created_at",
+                        query.sort.as_deref().unwrap_or_default()
+                    )
+                })),
+            )
+                .into());
+        }
+    };
+
+    let fields = query
+        .fields
+        .as_deref()
+        .map(TaskFields::parse)
+        .transpose()
+        .map_err(|e| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "status": "fail",
+                    "error": e
+                })),
+            )
+        })?;
     
-    // Parse user_id string to UUID
-    let user_id = uuid::Uuid::parse_str(user_id_str).map_err(|e| {
-        error!(
-            user_id_str = %user_id_str,
-            error = %e,
-            "Failed to parse user_id as UUID"
-        );
-        (
+    debug!(
+        sort = ?query.sort,
+        "Listing tasks for user"
+    );
+
+    let tasks = sqlx::query_as::<_, Task>(sql)
+    .bind(user_id)
+    .bind(&tenant.0)
+    .fetch_all(&state.db_read)
+    .await
+    .map_err(|e| AppError::db(&state.db_read, e, "Failed to fetch tasks"))?;
+
+    info!(
+        task_count = tasks.len(),
+        "Tasks retrieved successfully"
+    );
+
+    let data = match fields {
+        Some(fields) => json!({
+            "total": tasks.len(),
+            "tasks": tasks
+                .into_iter()
+                .map(|task| fields.select(TaskResponse::from(task)))
+                .collect::<Vec<_>>(),
+        }),
+        None => json!(TaskListResponse::from(tasks)),
+    };
+
+    Ok(success(data))
+}
+
+const DEFAULT_RECENT_LIMIT: i64 = 5;
+const MAX_RECENT_LIMIT: i64 = 50;
+
+#[utoipa::path(
+    get,
+    path = "/api/tasks/recent",
+    tag = "tasks",
+    params(RecentTasksQuery),
+    responses(
+        (status = 200, description = "Most recently updated tasks", body = Success<TaskListResponse>),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn recent_tasks(
+    Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<RecentTasksQuery>,
+) -> Result<Json<Success<TaskListResponse>>, AppError> {
+    let user_id = current_user_id(&token)?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_RECENT_LIMIT)
+        .clamp(1, MAX_RECENT_LIMIT);
+
+    debug!(
+        limit,
+        "Listing recent tasks for user"
+    );
+
+    let tasks = sqlx::query_as::<_, Task>(queries::RECENT_TASKS)
+    .bind(user_id)
+    .bind(&tenant.0)
+    .bind(limit)
+    .fetch_all(&state.db_read)
+    .await
+    .map_err(|e| AppError::db(&state.db_read, e, "Failed to fetch tasks"))?;
+
+    Ok(success(TaskListResponse::from(tasks)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tasks/reorder",
+    tag = "tasks",
+    request_body = ReorderTasksSchema,
+    responses(
+        (status = 200, description = "Tasks in their new order", body = Success<TaskListResponse>),
+        (status = 400, description = "Duplicate task ids, or more than MAX_BATCH_ITEMS"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "A task id is not owned by the caller"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn reorder_tasks(
+    Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    mut tx: Tx,
+    Json(payload): Json<ReorderTasksSchema>,
+) -> Result<Json<Success<TaskListResponse>>, AppError> {
+    let user_id = current_user_id(&token)?;
+
+    if let Err(e) = payload.validate(state.config.max_batch_items) {
+        warn!(error = %e, "Rejected invalid reorder");
+        return Err((
             StatusCode::BAD_REQUEST,
             Json(json!({
                 "status": "fail",
-                "error": "Invalid user ID format"
+                "error": e
             })),
         )
-    })?;
-    
+            .into());
+    }
+
     debug!(
-        user_id = %user_id,
-        "Listing tasks for user"
+        task_count = payload.task_ids.len(),
+        "Reordering tasks"
     );
 
-    let tasks = sqlx::query_as::<_, Task>(
-        "SELECT * FROM tasks WHERE user_id = $1"
-    )
-    .bind(user_id)
-    .fetch_all(&state.db)
-    .await
-    .map_err(|e| {
-        error!(
-            user_id = %user_id,
-            error = %e,
-            "Failed to fetch tasks from database"
+    // Lock the caller's rows so concurrent reorders can't interleave
+    let owned: Vec<uuid::Uuid> = sqlx::query_scalar(queries::LOCK_OWNED_TASKS)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .bind(&payload.task_ids)
+        .fetch_all(tx.conn().await?)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to reorder tasks"))?;
+
+    if owned.len() != payload.task_ids.len() {
+        let missing: Vec<_> = payload
+            .task_ids
+            .iter()
+            .filter(|id| !owned.contains(id))
+            .collect();
+        warn!(
+            missing = ?missing,
+            "Reorder references tasks not owned by the user"
         );
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
+        return Err((
+            StatusCode::NOT_FOUND,
             Json(json!({
                 "status": "fail",
-                "error": "Failed to fetch tasks",
-                "details": e.to_string()
+                "error": "Task not found",
+                "details": missing
             })),
         )
-    })?;
+            .into());
+    }
 
+    sqlx::query(queries::REORDER_TASKS)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .bind(&payload.task_ids)
+        .execute(tx.conn().await?)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to reorder tasks"))?;
+
+    let tasks = sqlx::query_as::<_, Task>(queries::TASKS_BY_POSITION)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .fetch_all(tx.conn().await?)
+        .await
+        .map_err(|e| AppError::db(&state.db, e, "Failed to reorder tasks"))?;
+
+    // Committed by the transaction middleware once this returns 2xx
     info!(
-        user_id = %user_id,
-        task_count = tasks.len(),
-        "Tasks retrieved successfully"
+        task_count = payload.task_ids.len(),
+        "Tasks reordered successfully"
     );
 
-    Ok(Json(json!({
-        "status": "success",
-        "data": TaskListResponse::from(tasks)
-    })))
+    Ok(success(TaskListResponse::from(tasks)))
 }
 
 #[utoipa::path(
@@ -186,9 +406,11 @@ pub async fn list_tasks(
     ),
     responses(
         (status = 204, description = "Task deleted successfully"),
+        (status = 200, description = "Task deleted, instead of 204 when DELETE_RETURNS_BODY=true", body = serde_json::Value, example = json!({"status": "success"})),
         (status = 401, description = "Unauthorized"),
         (status = 404, description = "Task not found"),
-        (status = 500, description = "Internal server error")
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
     ),
     security(
         ("api_jwt_token" = [])
@@ -196,77 +418,121 @@ pub async fn list_tasks(
 )]
 pub async fn delete_task(
     Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
     State(state): State<Arc<AppState>>,
-    Path(id): Path<uuid::Uuid>,
-) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
-    let user_id_str = &token.subject;
-    
-    // Parse user_id string to UUID
-    let user_id = uuid::Uuid::parse_str(user_id_str).map_err(|e| {
-        error!(
-            user_id_str = %user_id_str,
-            error = %e,
-            "Failed to parse user_id as UUID"
-        );
-        (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "status": "fail",
-                "error": "Invalid user ID format"
-            })),
-        )
-    })?;
+    IdPath(id): IdPath,
+) -> Result<Response, AppError> {
+    let user_id = current_user_id(&token)?;
     
     debug!(
-        user_id = %user_id,
         task_id = %id,
         "Attempting to delete task"
     );
 
-    let result = sqlx::query(
-        "DELETE FROM tasks WHERE id = $1 AND user_id = $2"
+    delete_owned_task(&state.db, id, user_id, &tenant).await?;
+
+    info!(
+        task_id = %id,
+        "Task deleted successfully"
+    );
+
+    Ok(deleted(state.config.delete_returns_body))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/tasks/search",
+    tag = "tasks",
+    request_body = TaskSearchSchema,
+    responses(
+        (status = 200, description = "One page of the caller's matching tasks", body = Success<TaskPage>),
+        (status = 400, description = "Unsupported sort, an empty created_range, or a `status`, `priority` or `tags` filter (tasks have none of these)"),
+        (status = 401, description = "Unauthorized"),
+        (status = 422, description = "Unknown or malformed search fields"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
     )
-    .bind(id)
-    .bind(user_id)
-    .execute(&state.db)
-    .await
-    .map_err(|e| {
-        error!(
-            user_id = %user_id,
-            task_id = %id,
-            error = %e,
-            "Failed to delete task from database"
-        );
+)]
+pub async fn search_tasks(
+    Extension(token): Extension<axum_keycloak_auth::decode::KeycloakToken<Role>>,
+    tenant: Tenant,
+    State(state): State<Arc<AppState>>,
+    Json(search): Json<TaskSearchSchema>,
+) -> Result<Json<Success<TaskPage>>, AppError> {
+    let user_id = current_user_id(&token)?;
+
+    let bad_request = |error: String| -> AppError {
         (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            StatusCode::BAD_REQUEST,
             Json(json!({
                 "status": "fail",
-                "error": "Failed to delete task",
-                "details": e.to_string()
+                "error": error
             })),
         )
-    })?;
-
-    if result.rows_affected() == 0 {
-        warn!(
-            user_id = %user_id,
-            task_id = %id,
-            "Task not found for deletion"
-        );
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "status": "fail",
-                "error": "Task not found"
-            })),
-        ));
+            .into()
+    };
+    if let Some(field) = search.unsupported_filter() {
+        warn!(field, "Rejected search on an unsupported filter");
+        return Err(bad_request(format!("Filtering by '{}' is not supported: tasks have no {}", field, field)));
     }
+    let sort = search.sort().map_err(bad_request)?;
+    if let Some((Some(from), Some(to))) = search.created_range.as_ref().map(|r| (r.from, r.to)) {
+        if from > to {
+            return Err(bad_request("created_range.from must not be after created_range.to".to_string()));
+        }
+    }
+    let (page, per_page) = page_bounds(search.page, search.per_page);
+    let text_pattern = search.text_pattern();
+
+    debug!(
+        text = ?search.text,
+        sort = %sort,
+        page,
+        per_page,
+        "Searching tasks"
+    );
+
+    let (from, to) = search
+        .created_range
+        .as_ref()
+        .map_or((None, None), |range| (range.from, range.to));
+
+    let total: i64 = sqlx::query_scalar(queries::SEARCH_TASK_COUNT)
+        .bind(user_id)
+        .bind(&tenant.0)
+        .bind(&text_pattern)
+        .bind(from)
+        .bind(to)
+        .fetch_one(&state.db_read)
+        .await
+        .map_err(|e| AppError::db(&state.db_read, e, "Failed to search tasks"))?;
+
+    let tasks = sqlx::query_as::<_, Task>(sort.search_sql())
+        .bind(user_id)
+        .bind(&tenant.0)
+        .bind(&text_pattern)
+        .bind(from)
+        .bind(to)
+        .bind(per_page)
+        .bind((page - 1).saturating_mul(per_page))
+        .fetch_all(&state.db_read)
+        .await
+        .map_err(|e| AppError::db(&state.db_read, e, "Failed to search tasks"))?;
 
     info!(
-        user_id = %user_id,
-        task_id = %id,
-        "Task deleted successfully"
+        page,
+        task_count = tasks.len(),
+        total,
+        "Task search completed"
     );
 
-    Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+    Ok(success(TaskPage {
+        tasks: tasks.into_iter().map(TaskResponse::from).collect(),
+        page,
+        per_page,
+        total,
+    }))
+}
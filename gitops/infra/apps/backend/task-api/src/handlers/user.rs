@@ -1,70 +1,30 @@
-use crate::models::config::Config;
-use crate::models::{state::AppState, response::UserResponse};
+use crate::handlers::extract::IdPath;
+use crate::models::{
+    error::AppError,
+    query::UserListQuery,
+    response::{message, success, ActionMessage, Success, UserResponse},
+    role::Role,
+    state::AppState,
+    user::UpdateUserSchema,
+};
+use crate::queries;
 use axum::{
-    extract::{Path, State},
+    extract::{Query, State},
     http::StatusCode,
     Json,
 };
-use chrono::{TimeZone, Utc};
-use reqwest;
 use serde_json::json;
 use std::sync::Arc;
-use tracing::{info, warn, error, debug};
-
-async fn get_admin_token(config: &Config) -> Result<String, (StatusCode, Json<serde_json::Value>)> {
-    debug!("Requesting admin token from Keycloak");
-    
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/realms/{}/protocol/openid-connect/token",
-        config.keycloak_url,
-        config.realm
-    );
-    let mut params = std::collections::HashMap::new();
-    params.insert("grant_type", "client_credentials".to_string());
-    params.insert("client_id", config.admin_client_id.clone());
-    params.insert("client_secret", config.admin_client_secret.clone());
-
-    let res = client.post(&url)
-        .form(&params)
-        .send()
-        .await
-        .map_err(|e| {
-            error!(error = %e, "Failed to request admin token from Keycloak");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"status": "fail", "error": "Failed to get admin token", "details": e.to_string()})),
-            )
-        })?;
-
-    if !res.status().is_success() {
-        error!(status = %res.status(), "Invalid admin credentials for Keycloak");
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "fail", "error": "Invalid admin credentials"})),
-        ));
-    }
-
-    let token_res: serde_json::Value = res.json().await.map_err(|e| (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(json!({"status": "fail", "error": "Failed to parse token", "details": e.to_string()})),
-    ))?;
-
-    token_res["access_token"]
-        .as_str()
-        .map(|t| t.to_string())
-        .ok_or((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "fail", "error": "No access token in response"})),
-        ))
-}
+use tracing::{info, warn, debug};
 
 #[utoipa::path(
     get,
     path = "/api/admin/users",
     tag = "users",
+    params(UserListQuery),
     responses(
-        (status = 200, description = "List of users", body = [UserResponse]),
+        (status = 200, description = "List of users", body = Success<Vec<UserResponse>>),
+        (status = 400, description = "Unknown role filter"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
         (status = 500, description = "Internal server error")
@@ -75,66 +35,53 @@ async fn get_admin_token(config: &Config) -> Result<String, (StatusCode, Json<se
 )]
 pub async fn list_users(
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<UserResponse>>, (StatusCode, Json<serde_json::Value>)> {
-    debug!("Listing users from Keycloak");
-    
-    let token = get_admin_token(&state.config).await?;
-
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/admin/realms/{}/users",
-        state.config.keycloak_url, state.config.realm
-    );
-
-    let res = client.get(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .header("Accept", "application/json")
-        .send()
-        .await
+    Query(query): Query<UserListQuery>,
+) -> Result<Json<Success<Vec<UserResponse>>>, AppError> {
+    let role_filter = query
+        .role
+        .as_deref()
+        .map(str::parse::<Role>)
+        .transpose()
         .map_err(|e| {
-            error!(error = %e, "Failed to fetch users from Keycloak API");
+            warn!(error = %e, "Rejected user listing with unknown role filter");
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"status": "fail", "error": "Failed to fetch users from Keycloak", "details": e.to_string()})),
+                StatusCode::BAD_REQUEST,
+                Json(json!({"status": "fail", "error": e})),
             )
         })?;
 
-    if !res.status().is_success() {
-        error!(status = %res.status(), "Keycloak API error when fetching users");
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "fail", "error": "Keycloak API error"})),
-        ));
-    }
+    debug!(role = ?role_filter, "Listing users from Keycloak");
 
-    let kc_users: Vec<serde_json::Value> = res.json().await.map_err(|e| {
-        error!(error = %e, "Failed to parse users JSON from Keycloak");
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "fail", "error": "Failed to parse users", "details": e.to_string()})),
-        )
-    })?;
+    // Always the full listing: `user` means "not an admin", which Keycloak's role member lists can't express
+    let kc_users = state.keycloak.list_users(None).await?;
+
+    // The base representation has no roles, so take them from the admin role's members.
+    // If that lookup fails an unfiltered listing still succeeds, with every user flagged as unresolved;
+    // a filtered one can't tell admins apart, so it fails instead.
+    let admin_ids: Option<std::collections::HashSet<uuid::Uuid>> =
+        match state.keycloak.list_users(Some(&Role::Admin)).await {
+            Ok(admins) => Some(admins.into_iter().map(|u| u.id).collect()),
+            Err(e) if role_filter.is_some() => return Err(e),
+            Err(_) => {
+                warn!(
+                    user_count = kc_users.len(),
+                    "Failed to resolve admin role members, listing users with the default role"
+                );
+                None
+            }
+        };
 
     let user_responses: Vec<UserResponse> = kc_users
         .into_iter()
         .map(|u| {
-            let id_str = u["id"].as_str().unwrap_or("");
-            let id = uuid::Uuid::parse_str(id_str).unwrap_or(uuid::Uuid::nil());
-            let role = u["role"].to_string().into();
-            let name = u["username"].as_str().unwrap_or("unknown").to_string();
-            let email = u["email"].as_str().unwrap_or("").to_string();
-            let created_ts = u["createdTimestamp"].as_i64().unwrap_or(0);
-            let created_at = Some(Utc.timestamp_millis_opt(created_ts).unwrap());
-
-            UserResponse {
-                id,
-                name,
-                email,
-                role,
-                verified: true,
-                created_at,
-                updated_at: created_at,
-            }
+            let is_admin = admin_ids.as_ref().is_some_and(|ids| ids.contains(&u.id));
+            (if is_admin { Role::Admin } else { Role::User }, u)
+        })
+        .filter(|(role, _)| role_filter.as_ref().is_none_or(|filter| filter == role))
+        .map(|(role, u)| {
+            let mut response = u.into_response(&role);
+            response.role_resolution_error = admin_ids.is_none();
+            response
         })
         .collect();
 
@@ -143,18 +90,19 @@ pub async fn list_users(
         "Users retrieved successfully from Keycloak"
     );
 
-    Ok(Json(user_responses))
+    Ok(success(user_responses))
 }
 
 #[utoipa::path(
-    delete,
+    get,
     path = "/api/admin/users/{id}",
     tag = "users",
     params(
         ("id" = uuid::Uuid, Path, description = "User ID")
     ),
     responses(
-        (status = 200, description = "User deleted successfully"),
+        (status = 200, description = "The user", body = Success<UserResponse>),
+        (status = 400, description = "Malformed user ID"),
         (status = 401, description = "Unauthorized"),
         (status = 403, description = "Forbidden"),
         (status = 404, description = "User not found"),
@@ -164,92 +112,264 @@ pub async fn list_users(
         ("api_jwt_token" = [])
     )
 )]
+pub async fn get_user(
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+) -> Result<Json<Success<UserResponse>>, AppError> {
+    debug!(target_user_id = %id, "Fetching user from Keycloak");
+
+    let user = state.keycloak.get_user(id).await?;
+
+    // Resolved like list_users: from the admin role's members, falling back to `user`
+    let (role, role_resolution_error) = match state.keycloak.list_users(Some(&Role::Admin)).await {
+        Ok(admins) if admins.iter().any(|admin| admin.id == id) => (Role::Admin, false),
+        Ok(_) => (Role::User, false),
+        Err(_) => {
+            warn!(target_user_id = %id, "Failed to resolve admin role members, returning the default role");
+            (Role::User, true)
+        }
+    };
+    let mut response = user.into_response(&role);
+    response.role_resolution_error = role_resolution_error;
+
+    info!(target_user_id = %id, "User retrieved successfully from Keycloak");
+
+    Ok(success(response))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/admin/users/{id}",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User deleted successfully", body = Success<ActionMessage>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error"),
+        (status = 503, description = "Database temporarily unavailable")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
 pub async fn delete_user(
     State(state): State<Arc<AppState>>,
-    Path(id): Path<uuid::Uuid>,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    IdPath(id): IdPath,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
     debug!(
-        user_id = %id,
+        target_user_id = %id,
         "Attempting to delete user"
     );
     
-    let token = get_admin_token(&state.config).await?;
-
-    let client = reqwest::Client::new();
-    let url = format!(
-        "{}/admin/realms/{}/users/{}",
-        state.config.keycloak_url, state.config.realm, id
-    );
-
-    let res = client.delete(&url)
-        .header("Authorization", format!("Bearer {}", token))
-        .send()
-        .await
-        .map_err(|e| {
-            error!(
-                user_id = %id,
-                error = %e,
-                "Failed to delete user from Keycloak API"
-            );
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"status": "fail", "error": "Failed to delete user from Keycloak", "details": e.to_string()})),
-            )
-        })?;
-
-    if res.status() == StatusCode::NOT_FOUND {
-        warn!(
-            user_id = %id,
-            "User not found in Keycloak for deletion"
-        );
-        return Err((
-            StatusCode::NOT_FOUND,
-            Json(json!({"status": "fail", "error": "User not found in Keycloak"})),
-        ));
-    } else if !res.status().is_success() {
-        let status = res.status();
-        let text = res.text().await.unwrap_or_else(|_| "<no body>".to_string());
-        error!(
-            user_id = %id,
-            status = %status,
-            body = %text,
-            "Keycloak API error when deleting user"
-        );
-        return Err((
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"status": "fail", "error": "Keycloak API error", "details": text})),
-        ));
-    }    
+    state.keycloak.delete_user(id).await?;
 
     // Clean up tasks
     debug!(
-        user_id = %id,
+        target_user_id = %id,
         "Cleaning up user tasks from database"
     );
     
-    let result = sqlx::query("DELETE FROM tasks WHERE user_id = $1")
+    let result = sqlx::query(queries::DELETE_USER_TASKS)
         .bind(id)
         .execute(&state.db)
         .await
-        .map_err(|e| {
-            error!(
-                user_id = %id,
-                error = %e,
-                "Failed to clean up user tasks from database"
-            );
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"status": "fail", "error": "Failed to clean up tasks", "details": e.to_string()})),
-            )
-        })?;
+        .map_err(|e| AppError::db(&state.db, e, "Failed to clean up tasks"))?;
 
     info!(
-        user_id = %id,
+        target_user_id = %id,
         tasks_deleted = result.rows_affected(),
         "User and associated tasks deleted successfully"
     );
 
-    Ok(Json(
-        json!({"status": "success", "message": format!("User {} deleted successfully", id)}),
-    ))
+    Ok(message(format!("User {} deleted successfully", id)))
+}
+
+async fn set_user_enabled(
+    state: &AppState,
+    id: uuid::Uuid,
+    enabled: bool,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    debug!(
+        target_user_id = %id,
+        enabled = enabled,
+        "Updating user enabled flag in Keycloak"
+    );
+
+    state.keycloak.set_user_enabled(id, enabled).await?;
+
+    let action = if enabled { "enabled" } else { "disabled" };
+    info!(
+        target_user_id = %id,
+        enabled = enabled,
+        "User {} in Keycloak", action
+    );
+
+    Ok(message(format!("User {} {} successfully", id, action)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/disable",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User disabled; Keycloak rejects their logins", body = Success<ActionMessage>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn disable_user(
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    set_user_enabled(&state, id, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/enable",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "User re-enabled", body = Success<ActionMessage>),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn enable_user(
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    set_user_enabled(&state, id, true).await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/admin/users/{id}",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID")
+    ),
+    request_body = UpdateUserSchema,
+    responses(
+        (status = 200, description = "User updated; fields left out are unchanged", body = Success<ActionMessage>),
+        (status = 400, description = "No fields to update, or a malformed email"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "Another user already has the email"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn update_user(
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+    Json(mut payload): Json<UpdateUserSchema>,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    if let Err(e) = payload.validate() {
+        warn!(target_user_id = %id, error = %e, "Rejected invalid user update");
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({"status": "fail", "error": e})),
+        )
+            .into());
+    }
+
+    let representation = payload.representation();
+    debug!(
+        target_user_id = %id,
+        fields = ?representation.as_object().map(|fields| fields.keys().collect::<Vec<_>>()),
+        "Updating user in Keycloak"
+    );
+
+    state.keycloak.update_user(id, &representation).await?;
+
+    info!(target_user_id = %id, "User updated in Keycloak");
+
+    Ok(message(format!("User {} updated successfully", id)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/send-verify-email",
+    tag = "users",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Keycloak sent the user a verification email", body = Success<ActionMessage>),
+        (status = 400, description = "Keycloak refused, e.g. the user has no email address"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error, e.g. Keycloak can't send email")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn send_verify_email(
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    debug!(target_user_id = %id, "Requesting verification email from Keycloak");
+
+    state.keycloak.send_verify_email(id).await?;
+
+    Ok(message(format!("Verification email sent to user {}", id)))
+}
+
+/// Required action Keycloak's email asks the user to complete
+const UPDATE_PASSWORD: &str = "UPDATE_PASSWORD";
+
+#[utoipa::path(
+    post,
+    path = "/api/admin/users/{id}/reset-password-email",
+    tag = "users",
+    description = "Has Keycloak email the user a link to its `UPDATE_PASSWORD` required action (the execute-actions-email endpoint), so they can choose a new password.",
+    params(
+        ("id" = uuid::Uuid, Path, description = "User ID")
+    ),
+    responses(
+        (status = 200, description = "Keycloak sent the user a password reset email", body = Success<ActionMessage>),
+        (status = 400, description = "Keycloak refused, e.g. the user has no email address"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "User not found"),
+        (status = 500, description = "Internal server error, e.g. Keycloak can't send email")
+    ),
+    security(
+        ("api_jwt_token" = [])
+    )
+)]
+pub async fn send_reset_password_email(
+    State(state): State<Arc<AppState>>,
+    IdPath(id): IdPath,
+) -> Result<Json<Success<ActionMessage>>, AppError> {
+    debug!(target_user_id = %id, "Requesting password reset email from Keycloak");
+
+    state.keycloak.execute_actions_email(id, &[UPDATE_PASSWORD]).await?;
+
+    Ok(message(format!("Password reset email sent to user {}", id)))
 }
@@ -0,0 +1,80 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::{sync::watch, task::JoinHandle, time::Instant};
+use tracing::{info, warn};
+
+/// A background loop run by [`Jobs`]: `tick` every `interval`, then `stop` once on shutdown
+#[async_trait]
+pub trait Job: Send + 'static {
+    fn name(&self) -> &'static str;
+
+    fn interval(&self) -> Duration;
+
+    async fn tick(&mut self);
+
+    /// Runs after the last tick when the app shuts down
+    async fn stop(&mut self) {}
+}
+
+/// Owns the app's background jobs so each feature doesn't reinvent spawn/shutdown plumbing
+pub struct Jobs {
+    stop: watch::Sender<bool>,
+    handles: Vec<(&'static str, JoinHandle<()>)>,
+}
+
+impl Default for Jobs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Jobs {
+    pub fn new() -> Self {
+        let (stop, _) = watch::channel(false);
+        Self { stop, handles: Vec::new() }
+    }
+
+    /// Spawns `job`; the first tick runs immediately
+    pub fn register<J: Job>(&mut self, mut job: J) {
+        let name = job.name();
+        let interval = job.interval();
+        let mut stopped = self.stop.subscribe();
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => job.tick().await,
+                    _ = stopped.changed() => break,
+                }
+            }
+            job.stop().await;
+        });
+
+        info!(job = name, interval_secs = interval.as_secs(), "Background job started");
+        self.handles.push((name, handle));
+    }
+
+    pub fn len(&self) -> usize {
+        self.handles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.handles.is_empty()
+    }
+
+    /// Signals every job to stop and waits up to `timeout` in total; jobs still running are aborted
+    pub async fn shutdown(self, timeout: Duration) {
+        let _ = self.stop.send(true);
+        let deadline = Instant::now() + timeout;
+        for (name, mut handle) in self.handles {
+            match tokio::time::timeout_at(deadline, &mut handle).await {
+                Ok(Ok(())) => info!(job = name, "Background job stopped"),
+                Ok(Err(e)) => warn!(job = name, "Background job failed: {}", e),
+                Err(_) => {
+                    handle.abort();
+                    warn!(job = name, timeout_secs = timeout.as_secs(), "Background job did not stop in time, aborted");
+                }
+            }
+        }
+    }
+}
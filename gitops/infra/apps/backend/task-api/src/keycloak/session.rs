@@ -0,0 +1,36 @@
+use crate::models::response::SessionResponse;
+use chrono::{TimeZone, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// The fields we use from Keycloak's admin API `UserSessionRepresentation`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeycloakSession {
+    pub id: String,
+    pub ip_address: Option<String>,
+    /// Milliseconds since the Unix epoch
+    pub start: Option<i64>,
+    /// Milliseconds since the Unix epoch
+    pub last_access: Option<i64>,
+    /// Client UUID to client id
+    #[serde(default)]
+    pub clients: HashMap<String, String>,
+}
+
+impl KeycloakSession {
+    pub fn into_response(self) -> SessionResponse {
+        let timestamp = |ms: Option<i64>| ms.and_then(|ms| Utc.timestamp_millis_opt(ms).single());
+
+        let mut clients: Vec<String> = self.clients.into_values().collect();
+        clients.sort();
+
+        SessionResponse {
+            id: self.id,
+            ip_address: self.ip_address,
+            started_at: timestamp(self.start),
+            last_access_at: timestamp(self.last_access),
+            clients,
+        }
+    }
+}
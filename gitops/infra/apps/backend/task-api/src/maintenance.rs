@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use sqlx::PgPool;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tracing::{info, warn};
+
+use crate::{jobs::Job, queries};
+
+/// `settings` row holding the maintenance switch shared by every replica
+pub const MAINTENANCE_KEY: &str = "maintenance_mode";
+
+/// The stored switch; `None` until an admin has flipped it, so `MAINTENANCE_MODE` applies
+pub async fn load(db: &PgPool) -> Result<Option<bool>, sqlx::Error> {
+    let value: Option<String> = sqlx::query_scalar(queries::SETTING)
+        .bind(MAINTENANCE_KEY)
+        .fetch_optional(db)
+        .await?;
+    Ok(value.map(|value| value == "true"))
+}
+
+pub async fn store(db: &PgPool, enabled: bool) -> Result<(), sqlx::Error> {
+    sqlx::query(queries::UPSERT_SETTING)
+        .bind(MAINTENANCE_KEY)
+        .bind(enabled.to_string())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Background job copying the stored switch into this pod's flag every `interval`, so a
+/// toggle sent to one replica reaches the others. Keeps the last value while the database is down
+pub struct MaintenanceSync {
+    db: PgPool,
+    flag: Arc<AtomicBool>,
+    default: bool,
+    interval: Duration,
+}
+
+impl MaintenanceSync {
+    pub fn new(db: PgPool, flag: Arc<AtomicBool>, default: bool, interval: Duration) -> Self {
+        Self { db, flag, default, interval }
+    }
+}
+
+#[async_trait]
+impl Job for MaintenanceSync {
+    fn name(&self) -> &'static str {
+        "maintenance_sync"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn tick(&mut self) {
+        match load(&self.db).await {
+            Ok(stored) => {
+                let enabled = stored.unwrap_or(self.default);
+                if self.flag.swap(enabled, Ordering::Relaxed) != enabled {
+                    info!(enabled, "Maintenance mode {} from shared settings", if enabled { "enabled" } else { "disabled" });
+                }
+            }
+            Err(e) => warn!("Failed to read maintenance mode, keeping the current value: {}", e),
+        }
+    }
+}
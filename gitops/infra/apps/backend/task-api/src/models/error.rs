@@ -0,0 +1,87 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::{error, warn};
+
+// How long clients are asked to back off when the connection pool is exhausted
+const POOL_RETRY_AFTER_SECS: u64 = 5;
+// How long clients are asked to back off when Keycloak does not answer in time
+const KEYCLOAK_RETRY_AFTER_SECS: u64 = 5;
+// In-flight requests finish quickly, so a shed client can try again almost at once
+const OVERLOADED_RETRY_AFTER_SECS: u64 = 1;
+
+pub enum AppError {
+    Response(StatusCode, Json<serde_json::Value>),
+    ServiceUnavailable { retry_after_secs: u64 },
+}
+
+impl AppError {
+    /// A failed query on `db`: 503 with `Retry-After` when its pool is exhausted, otherwise
+    /// a logged 500 with `message` as the error and the database's own error as details
+    pub fn db(db: &PgPool, error: sqlx::Error, message: &str) -> Self {
+        if matches!(error, sqlx::Error::PoolTimedOut) {
+            return Self::pool_timed_out(db);
+        }
+        error!(error = %error, "{}", message);
+        AppError::Response(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "status": "fail",
+                "error": message,
+                "details": error.to_string()
+            })),
+        )
+    }
+
+    pub fn pool_timed_out(db: &PgPool) -> Self {
+        warn!(
+            pool_size = db.size(),
+            idle_connections = db.num_idle(),
+            "Database pool exhausted, asking client to retry"
+        );
+        AppError::ServiceUnavailable {
+            retry_after_secs: POOL_RETRY_AFTER_SECS,
+        }
+    }
+
+    pub fn keycloak_timed_out(error: &reqwest::Error) -> Self {
+        warn!(error = %error, "Keycloak request timed out, asking client to retry");
+        AppError::ServiceUnavailable {
+            retry_after_secs: KEYCLOAK_RETRY_AFTER_SECS,
+        }
+    }
+
+    pub fn overloaded() -> Self {
+        warn!("Concurrency limit reached, shedding request");
+        AppError::ServiceUnavailable {
+            retry_after_secs: OVERLOADED_RETRY_AFTER_SECS,
+        }
+    }
+}
+
+impl From<(StatusCode, Json<serde_json::Value>)> for AppError {
+    fn from((status, body): (StatusCode, Json<serde_json::Value>)) -> Self {
+        AppError::Response(status, body)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match self {
+            AppError::Response(status, body) => (status, body).into_response(),
+            AppError::ServiceUnavailable { retry_after_secs } => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                Json(json!({
+                    "status": "fail",
+                    "error": "Service temporarily unavailable"
+                })),
+            )
+                .into_response(),
+        }
+    }
+}
@@ -1,18 +1,201 @@
+use async_trait::async_trait;
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
-use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use std::any::Any;
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::fs;
 use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use axum::http::StatusCode;
+
+use crate::jobs::Job;
+use crate::models::logfmt::{Logfmt, LogfmtFields};
+
+static SAMPLER: OnceLock<LogSampler> = OnceLock::new();
+static SLOW_REQUEST_THRESHOLD: OnceLock<Option<Duration>> = OnceLock::new();
+static QUIET_PATHS: OnceLock<QuietPaths> = OnceLock::new();
+static LOG_HEALTH_CHECKS: OnceLock<bool> = OnceLock::new();
+static BASE_PATH: OnceLock<String> = OnceLock::new();
+
+/// The probe routes kept out of the request log unless `LOG_HEALTH_CHECKS=true`
+pub const HEALTH_CHECK_PATHS: [&str; 2] = ["/api/health", "/api/health/ready"];
 
 #[derive(Debug, Clone)]
 pub struct LoggingConfig {
     pub level: String,
     pub format: LogFormat,
     pub output: LogOutput,
+    /// How often file output starts a new file
+    pub rotation: LogRotation,
+    /// File name of the log files; rotated ones get a `.<date>` suffix
+    pub file_prefix: String,
+    /// Rotated files kept by [`LogRetention`]; `None` keeps them all
+    pub max_files: Option<usize>,
+    pub sampling: LogSampling,
+    /// Requests slower than this are logged at warn whatever their status
+    pub slow_request_ms: Option<u64>,
+    pub quiet_paths: QuietPaths,
+    /// Whether health probe requests get request log lines at the usual levels
+    pub health_checks: bool,
 }
 
+/// Routes whose completion lines are logged at debug unless they fail with a 5xx.
+/// Entries match with or without the `BASE_PATH` prefix.
+#[derive(Debug, Clone, Default)]
+pub struct QuietPaths(pub Vec<String>);
+
+impl QuietPaths {
+    pub fn parse(value: &str) -> Self {
+        Self(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(String::from)
+                .collect(),
+        )
+    }
+
+    pub fn is_quiet(&self, path: &str, status: StatusCode) -> bool {
+        let path = route_path(path);
+        !status.is_server_error() && self.0.iter().any(|p| route_path(p) == path)
+    }
+}
+
+/// Completion logs kept per status class: 1 in N. Errors are always logged.
 #[derive(Debug, Clone)]
+pub struct LogSampling {
+    pub success_every: u64,
+    pub redirect_every: u64,
+}
+
+impl Default for LogSampling {
+    fn default() -> Self {
+        Self { success_every: 1, redirect_every: 1 }
+    }
+}
+
+#[derive(Debug)]
+pub struct LogSampler {
+    sampling: LogSampling,
+    success_seen: AtomicU64,
+    redirect_seen: AtomicU64,
+}
+
+impl LogSampler {
+    pub fn new(sampling: LogSampling) -> Self {
+        Self {
+            sampling,
+            success_seen: AtomicU64::new(0),
+            redirect_seen: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the completion line for a response with this status should be logged
+    pub fn should_log(&self, status: StatusCode) -> bool {
+        let (seen, every) = match status.as_u16() {
+            200..=299 => (&self.success_seen, self.sampling.success_every),
+            300..=399 => (&self.redirect_seen, self.sampling.redirect_every),
+            _ => return true,
+        };
+        every <= 1 || seen.fetch_add(1, Ordering::Relaxed) % every == 0
+    }
+}
+
+/// Threshold installed by [`LoggingConfig::init`]; `None` disables slow-request warnings
+pub fn slow_request_threshold() -> Option<Duration> {
+    *SLOW_REQUEST_THRESHOLD.get_or_init(|| None)
+}
+
+/// Quiet paths installed by [`LoggingConfig::init`]; none until then
+pub fn quiet_paths() -> &'static QuietPaths {
+    QUIET_PATHS.get_or_init(QuietPaths::default)
+}
+
+/// Whether [`HEALTH_CHECK_PATHS`] are logged like other routes; off until [`LoggingConfig::init`]
+pub fn log_health_checks() -> bool {
+    *LOG_HEALTH_CHECKS.get_or_init(|| false)
+}
+
+/// Sets the `BASE_PATH` the router is nested under, so route checks see the same paths with or
+/// without it. `BASE_PATH` lives in [`Config`](crate::models::config::Config), which is loaded after
+/// logging, so this is set once at startup before any request is served.
+pub fn set_base_path(base_path: &str) {
+    let _ = BASE_PATH.set(base_path.to_string());
+}
+
+/// `path` without the `BASE_PATH` prefix; paths outside it are returned unchanged
+pub fn route_path(path: &str) -> &str {
+    match BASE_PATH.get().filter(|base| !base.is_empty()) {
+        Some(base) => match path.strip_prefix(base.as_str()) {
+            Some(rest) if rest.starts_with('/') => rest,
+            _ => path,
+        },
+        None => path,
+    }
+}
+
+pub fn is_health_check(path: &str) -> bool {
+    HEALTH_CHECK_PATHS.contains(&route_path(path))
+}
+
+/// The message a panic was raised with, for the usual `panic!("...")` payloads
+pub fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("non-string panic payload")
+}
+
+// Replaces the default hook, which writes a raw message and backtrace to stderr, with an
+// error event; the backtrace is attached when RUST_BACKTRACE asks for one
+fn log_panics() {
+    std::panic::set_hook(Box::new(|info| {
+        let location = info.location().map(|l| format!("{}:{}", l.file(), l.line())).unwrap_or_default();
+        let backtrace = Backtrace::capture();
+        if backtrace.status() == BacktraceStatus::Captured {
+            error!(
+                location = location,
+                panic = panic_message(info.payload()),
+                backtrace = %backtrace,
+                "Thread panicked"
+            );
+        } else {
+            error!(location = location, panic = panic_message(info.payload()), "Thread panicked");
+        }
+    }));
+}
+
+/// Sampler installed by [`LoggingConfig::init`]; logs everything until then
+pub fn sampler() -> &'static LogSampler {
+    SAMPLER.get_or_init(|| LogSampler::new(LogSampling::default()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum LogFormat {
     Json,
     Pretty,
+    /// Single-line `key=value` pairs, see [`Logfmt`]
+    Logfmt,
+}
+
+impl LogFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogFormat::Json => "json",
+            LogFormat::Pretty => "pretty",
+            LogFormat::Logfmt => "logfmt",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -21,24 +204,189 @@ pub enum LogOutput {
     File { directory: String },
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+impl LogRotation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogRotation::Hourly => "hourly",
+            LogRotation::Daily => "daily",
+            LogRotation::Never => "never",
+        }
+    }
+
+    fn rotation(self) -> Rotation {
+        match self {
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+// How often LogRetention looks for files to prune; the first check runs at startup
+const LOG_RETENTION_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Deletes rotated log files beyond the newest `keep`, since the appender never does
+pub struct LogRetention {
+    directory: PathBuf,
+    prefix: String,
+    keep: usize,
+}
+
+#[async_trait]
+impl Job for LogRetention {
+    fn name(&self) -> &'static str {
+        "log_retention"
+    }
+
+    fn interval(&self) -> Duration {
+        LOG_RETENTION_INTERVAL
+    }
+
+    async fn tick(&mut self) {
+        match prune_log_files(&self.directory, &self.prefix, self.keep) {
+            Ok(removed) if !removed.is_empty() => {
+                info!(removed = removed.len(), keep = self.keep, "Pruned old log files")
+            }
+            Ok(_) => {}
+            Err(e) => warn!(directory = %self.directory.display(), "Failed to prune old log files: {}", e),
+        }
+    }
+}
+
+/// Removes all but the newest `keep` rotated files named `<prefix>.<date>` in `directory`
+/// and returns what it removed. The date suffixes sort chronologically, so the names alone
+/// decide which are newest; the current file of `never` rotation and unrelated files are left alone.
+pub fn prune_log_files(directory: &Path, prefix: &str, keep: usize) -> io::Result<Vec<PathBuf>> {
+    let mut rotated: Vec<(String, PathBuf)> = fs::read_dir(directory)?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let suffix = name.strip_prefix(prefix)?.strip_prefix('.')?;
+            let is_date = !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit() || c == '-');
+            is_date.then(|| (name, entry.path()))
+        })
+        .collect();
+    rotated.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let mut removed = Vec::new();
+    for (_, path) in rotated.into_iter().skip(keep) {
+        fs::remove_file(&path)?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+// A whole number of at least 1, or None when unset or invalid, recording the latter
+fn positive<T: FromStr + Default + PartialOrd>(key: &str, value: Option<String>, problems: &mut Vec<String>) -> Option<T> {
+    let value = value?;
+    match value.parse() {
+        Ok(n) if n > T::default() => Some(n),
+        _ => {
+            problems.push(format!("{} must be a positive whole number, got '{}'", key, value));
+            None
+        }
+    }
+}
+
 impl LoggingConfig {
-    pub fn from_env() -> Self {
-        let level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
-        
-        let format = match std::env::var("LOG_FORMAT").unwrap_or_else(|_| "pretty".to_string()).as_str() {
-            "json" => LogFormat::Json,
-            _ => LogFormat::Pretty,
+    /// Reads the logging env vars. Invalid values fall back to their defaults so logging can
+    /// still start, and are returned as problems for main to report with the `Config` ones
+    pub fn from_env() -> (Self, Vec<String>) {
+        Self::from_vars(|key| std::env::var(key).ok())
+    }
+
+    /// [`from_env`](Self::from_env) with the variables looked up through `var`
+    pub fn from_vars(var: impl Fn(&str) -> Option<String>) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        // Blank counts as unset, as with an empty `env:` entry in a manifest
+        let set = |key: &str| var(key).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+
+        let level = var("LOG_LEVEL").unwrap_or_else(|| "info".to_string());
+
+        let format = match set("LOG_FORMAT").as_deref() {
+            None | Some("pretty") => LogFormat::Pretty,
+            Some("json") => LogFormat::Json,
+            Some("logfmt") => LogFormat::Logfmt,
+            Some(other) => {
+                problems.push(format!("LOG_FORMAT must be one of json, logfmt, pretty, got '{}'", other));
+                LogFormat::Pretty
+            }
         };
 
-        let output = match std::env::var("LOG_OUTPUT") {
-            Ok(path) if path != "stdout" => LogOutput::File { directory: path },
+        let output = match var("LOG_OUTPUT") {
+            Some(path) if path != "stdout" => LogOutput::File { directory: path },
             _ => LogOutput::Stdout,
         };
 
-        Self { level, format, output }
+        let rotation = match set("LOG_ROTATION").map(|v| v.to_lowercase()).as_deref() {
+            None | Some("daily") => LogRotation::Daily,
+            Some("hourly") => LogRotation::Hourly,
+            Some("never") => LogRotation::Never,
+            Some(other) => {
+                problems.push(format!("LOG_ROTATION must be one of hourly, daily, never, got '{}'", other));
+                LogRotation::Daily
+            }
+        };
+        let file_prefix = var("LOG_FILE_PREFIX")
+            .filter(|prefix| !prefix.trim().is_empty())
+            .unwrap_or_else(|| "task-api.log".to_string());
+        let max_files = positive("LOG_MAX_FILES", set("LOG_MAX_FILES"), &mut problems);
+
+        let sampling = LogSampling {
+            success_every: positive("LOG_SAMPLE_2XX", set("LOG_SAMPLE_2XX"), &mut problems).unwrap_or(1),
+            redirect_every: positive("LOG_SAMPLE_3XX", set("LOG_SAMPLE_3XX"), &mut problems).unwrap_or(1),
+        };
+
+        let slow_request_ms = var("SLOW_REQUEST_MS").and_then(|v| v.parse().ok());
+
+        let quiet_paths = var("LOG_QUIET_PATHS")
+            .map(|v| QuietPaths::parse(&v))
+            .unwrap_or_default();
+
+        let health_checks = var("LOG_HEALTH_CHECKS").and_then(|v| v.parse().ok()).unwrap_or(false);
+
+        let config = Self {
+            level,
+            format,
+            output,
+            rotation,
+            file_prefix,
+            max_files,
+            sampling,
+            slow_request_ms,
+            quiet_paths,
+            health_checks,
+        };
+        (config, problems)
+    }
+
+    /// The pruning job for file output with `LOG_MAX_FILES` set
+    pub fn retention(&self) -> Option<LogRetention> {
+        match (&self.output, self.max_files) {
+            (LogOutput::File { directory }, Some(keep)) => Some(LogRetention {
+                directory: PathBuf::from(directory),
+                prefix: self.file_prefix.clone(),
+                keep,
+            }),
+            _ => None,
+        }
     }
 
     pub fn init(&self) -> Option<WorkerGuard> {
+        let _ = SAMPLER.set(LogSampler::new(self.sampling.clone()));
+        let _ = SLOW_REQUEST_THRESHOLD.set(self.slow_request_ms.map(Duration::from_millis));
+        let _ = QUIET_PATHS.set(self.quiet_paths.clone());
+        let _ = LOG_HEALTH_CHECKS.set(self.health_checks);
+        log_panics();
+
         let env_filter = EnvFilter::try_from_default_env()
             .unwrap_or_else(|_| EnvFilter::new(&self.level));
 
@@ -53,6 +401,17 @@ impl LoggingConfig {
                     .init();
                 None
             },
+            (LogFormat::Logfmt, LogOutput::Stdout) => {
+                tracing_subscriber::registry()
+                    .with(
+                        tracing_subscriber::fmt::layer()
+                            .fmt_fields(LogfmtFields)
+                            .event_format(Logfmt)
+                            .with_filter(env_filter)
+                    )
+                    .init();
+                None
+            },
             (LogFormat::Pretty, LogOutput::Stdout) => {
                 tracing_subscriber::registry()
                     .with(
@@ -64,7 +423,7 @@ impl LoggingConfig {
                 None
             },
             (LogFormat::Json, LogOutput::File { directory }) => {
-                let file_appender = tracing_appender::rolling::daily(directory, "task-api.log");
+                let file_appender = RollingFileAppender::new(self.rotation.rotation(), directory, &self.file_prefix);
                 let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
                 
                 tracing_subscriber::registry()
@@ -83,8 +442,29 @@ impl LoggingConfig {
                     .init();
                 Some(guard)
             },
+            (LogFormat::Logfmt, LogOutput::File { directory }) => {
+                let file_appender = RollingFileAppender::new(self.rotation.rotation(), directory, &self.file_prefix);
+                let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+
+                tracing_subscriber::registry()
+                    .with(
+                        tracing_subscriber::fmt::layer()
+                            .fmt_fields(LogfmtFields)
+                            .event_format(Logfmt)
+                            .with_writer(non_blocking)
+                            .with_filter(env_filter.clone())
+                    )
+                    .with(
+                        tracing_subscriber::fmt::layer()
+                            .compact()
+                            .with_writer(io::stdout)
+                            .with_filter(EnvFilter::new("info"))
+                    )
+                    .init();
+                Some(guard)
+            },
             (LogFormat::Pretty, LogOutput::File { directory }) => {
-                let file_appender = tracing_appender::rolling::daily(directory, "task-api.log");
+                let file_appender = RollingFileAppender::new(self.rotation.rotation(), directory, &self.file_prefix);
                 let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
                 
                 tracing_subscriber::registry()
@@ -1,6 +1,11 @@
+pub mod client_ip;
 pub mod config;
+pub mod error;
+pub mod logfmt;
 pub mod logging;
+pub mod query;
 pub mod task;
 pub mod response;
 pub mod state;
 pub mod role;
+pub mod user;
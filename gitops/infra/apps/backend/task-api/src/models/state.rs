@@ -1,5 +1,15 @@
+use crate::keycloak::KeycloakClient;
+use std::sync::{atomic::AtomicBool, Arc};
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: sqlx::PgPool,
+    /// Pool for read-only queries: the `DATABASE_READ_URL` replica, or the primary pool when unset.
+    /// Replicas lag, so anything read back right after a write must use `db`.
+    pub db_read: sqlx::PgPool,
     pub config: crate::models::config::Config,
+    pub keycloak: Arc<dyn KeycloakClient>,
+    /// Answer user routes with 503 while set; starts from `MAINTENANCE_MODE` and is
+    /// flipped at runtime through `PUT /api/admin/maintenance`
+    pub maintenance: Arc<AtomicBool>,
 }
\ No newline at end of file
@@ -0,0 +1,181 @@
+//! Converts the OpenAPI document into a Postman v2.1 collection, served next to it
+//! so the API can be imported into Postman in one step.
+
+use serde_json::{json, Map, Value};
+use utoipa::openapi::OpenApi;
+
+const SCHEMA_URL: &str = "https://schema.getpostman.com/json/collection/v2.1.0/collection.json";
+const METHODS: [&str; 8] = ["get", "post", "put", "patch", "delete", "head", "options", "trace"];
+
+/// One folder per tag holding a request per operation, in document order. Requests use
+/// `{{baseUrl}}` and inherit collection-level bearer auth reading `{{bearerToken}}`.
+pub fn collection(openapi: &OpenApi) -> Value {
+    let doc = serde_json::to_value(openapi).unwrap_or_default();
+    let schemas = &doc["components"]["schemas"];
+
+    let mut folders: Vec<(String, Vec<Value>)> = doc["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| tag["name"].as_str())
+        .map(|name| (name.to_string(), Vec::new()))
+        .collect();
+
+    for (path, item) in doc["paths"].as_object().into_iter().flatten() {
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            let tag = operation["tags"][0].as_str().unwrap_or("default");
+            let request = request_item(path, method, operation, schemas);
+            match folders.iter_mut().find(|(name, _)| name == tag) {
+                Some((_, items)) => items.push(request),
+                None => folders.push((tag.to_string(), vec![request])),
+            }
+        }
+    }
+
+    let base_url = doc["servers"][0]["url"].as_str().unwrap_or_default();
+    json!({
+        "info": {
+            "name": doc["info"]["title"],
+            "description": doc["info"]["description"],
+            "version": doc["info"]["version"],
+            "schema": SCHEMA_URL,
+        },
+        "auth": {
+            "type": "bearer",
+            "bearer": [{ "key": "token", "value": "{{bearerToken}}", "type": "string" }],
+        },
+        "variable": [
+            { "key": "baseUrl", "value": base_url },
+            { "key": "bearerToken", "value": "" },
+        ],
+        "item": folders
+            .into_iter()
+            .filter(|(_, items)| !items.is_empty())
+            .map(|(name, items)| json!({ "name": name, "item": items }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn request_item(path: &str, method: &str, operation: &Value, schemas: &Value) -> Value {
+    // `{id}` becomes Postman's `:id` path variable
+    let segments: Vec<String> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+            Some(name) => format!(":{}", name),
+            None => segment.to_string(),
+        })
+        .collect();
+
+    let parameters = operation["parameters"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let of_kind = |kind: &str| {
+        parameters
+            .iter()
+            .filter(|param| param["in"] == kind)
+            .map(|param| {
+                let mut entry = json!({
+                    "key": param["name"],
+                    "value": "",
+                    "description": param["description"].as_str().unwrap_or_default(),
+                });
+                if kind == "query" && param["required"] != true {
+                    entry["disabled"] = json!(true);
+                }
+                entry
+            })
+            .collect::<Vec<_>>()
+    };
+    let query = of_kind("query");
+    let variables = of_kind("path");
+
+    let mut raw = format!("{{{{baseUrl}}}}/{}", segments.join("/"));
+    if !query.is_empty() {
+        let keys: Vec<&str> = query.iter().filter_map(|q| q["key"].as_str()).collect();
+        raw = format!("{}?{}=", raw, keys.join("=&"));
+    }
+
+    let mut request = json!({
+        "method": method.to_uppercase(),
+        "header": [],
+        "url": {
+            "raw": raw,
+            "host": ["{{baseUrl}}"],
+            "path": segments,
+            "query": query,
+            "variable": variables,
+        },
+    });
+    if let Some(description) = operation["description"].as_str() {
+        request["description"] = json!(description);
+    }
+    if let Some(body) = request_body(&operation["requestBody"]["content"], schemas) {
+        if body["mode"] == "raw" {
+            request["header"] = json!([{ "key": "Content-Type", "value": "application/json" }]);
+        }
+        request["body"] = body;
+    }
+    // An explicit empty security list marks a public operation
+    if operation["security"].as_array().is_some_and(|s| s.is_empty()) {
+        request["auth"] = json!({ "type": "noauth" });
+    }
+
+    let name = operation["summary"]
+        .as_str()
+        .or_else(|| operation["operationId"].as_str())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} {}", method.to_uppercase(), path));
+    json!({ "name": name, "request": request })
+}
+
+fn request_body(content: &Value, schemas: &Value) -> Option<Value> {
+    if let Some(media) = content.get("application/json") {
+        let example = skeleton(&media["schema"], schemas);
+        return Some(json!({
+            "mode": "raw",
+            "raw": serde_json::to_string_pretty(&example).unwrap_or_default(),
+            "options": { "raw": { "language": "json" } },
+        }));
+    }
+    if content.get("multipart/form-data").is_some() {
+        // The import endpoint takes its CSV in a `file` field
+        return Some(json!({
+            "mode": "formdata",
+            "formdata": [{ "key": "file", "type": "file", "src": [] }],
+        }));
+    }
+    None
+}
+
+// A placeholder body with the schema's top-level properties, following one `$ref`
+fn skeleton(schema: &Value, schemas: &Value) -> Value {
+    let schema = match schema["$ref"].as_str().and_then(|r| r.rsplit('/').next()) {
+        Some(name) => &schemas[name],
+        None => schema,
+    };
+    let Some(properties) = schema["properties"].as_object() else {
+        return json!({});
+    };
+
+    let fields: Map<String, Value> = properties
+        .iter()
+        .map(|(name, property)| {
+            let kind = match &property["type"] {
+                Value::Array(kinds) => kinds.iter().find(|k| *k != "null").cloned().unwrap_or_default(),
+                kind => kind.clone(),
+            };
+            let placeholder = match kind.as_str() {
+                Some("integer") | Some("number") => json!(0),
+                Some("boolean") => json!(false),
+                Some("array") => json!([]),
+                Some("object") => json!({}),
+                Some("string") => json!(""),
+                _ => Value::Null,
+            };
+            (name.clone(), placeholder)
+        })
+        .collect();
+    Value::Object(fields)
+}
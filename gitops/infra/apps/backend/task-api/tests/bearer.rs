@@ -0,0 +1,63 @@
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderMap, StatusCode},
+    middleware,
+    routing::get,
+    Router,
+};
+use task_api::handlers::middleware::normalize_bearer;
+use tower::ServiceExt;
+
+// Echoes the Authorization header the auth layer would see
+async fn echo_authorization(headers: HeaderMap) -> String {
+    headers
+        .get(header::AUTHORIZATION)
+        .map(|v| v.to_str().unwrap().to_string())
+        .unwrap_or_default()
+}
+
+async fn send(authorization: Option<&str>) -> (StatusCode, Option<String>, String) {
+    let app = Router::new()
+        .route("/", get(echo_authorization))
+        .layer(middleware::from_fn(normalize_bearer));
+
+    let mut builder = Request::builder().uri("/");
+    if let Some(authorization) = authorization {
+        builder = builder.header(header::AUTHORIZATION, authorization);
+    }
+    let response = app.oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+    let status = response.status();
+    let challenge = response
+        .headers()
+        .get(header::WWW_AUTHENTICATE)
+        .map(|v| v.to_str().unwrap().to_string());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, challenge, String::from_utf8(bytes.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn well_formed_bearer_passes_unchanged() {
+    assert_eq!(send(Some("Bearer abc.def")).await, (StatusCode::OK, None, "Bearer abc.def".to_string()));
+}
+
+#[tokio::test]
+async fn scheme_casing_and_whitespace_are_normalized() {
+    assert_eq!(send(Some("bearer abc.def")).await.2, "Bearer abc.def");
+    assert_eq!(send(Some("  BEARER    abc.def  ")).await.2, "Bearer abc.def");
+}
+
+#[tokio::test]
+async fn missing_header_is_left_to_the_auth_layer() {
+    assert_eq!(send(None).await, (StatusCode::OK, None, String::new()));
+}
+
+#[tokio::test]
+async fn malformed_headers_are_rejected_with_401() {
+    for value in ["abc.def", "Bearer", "Bearer   ", "Basic dXNlcjpwYXNz", "Bearer abc def"] {
+        let (status, challenge, body) = send(Some(value)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", value);
+        assert_eq!(challenge.as_deref(), Some("Bearer"));
+        assert!(body.contains("Bearer <token>"));
+    }
+}
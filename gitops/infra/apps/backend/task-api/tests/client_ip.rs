@@ -0,0 +1,57 @@
+use axum::http::{HeaderMap, HeaderValue};
+use std::net::IpAddr;
+use task_api::models::client_ip::{Cidr, TrustedProxies};
+
+fn proxies(entries: &[&str]) -> TrustedProxies {
+    TrustedProxies(entries.iter().map(|e| e.parse().unwrap()).collect())
+}
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.append(*name, HeaderValue::from_str(value).unwrap());
+    }
+    headers
+}
+
+fn ip(value: &str) -> IpAddr {
+    value.parse().unwrap()
+}
+
+#[test]
+fn untrusted_peer_cannot_spoof_forwarding_headers() {
+    let trusted = proxies(&["10.0.0.0/8"]);
+    let spoofed = headers(&[("x-forwarded-for", "1.2.3.4"), ("forwarded", "for=5.6.7.8")]);
+
+    assert_eq!(trusted.resolve(ip("203.0.113.9"), &spoofed), ip("203.0.113.9"));
+    assert_eq!(TrustedProxies::default().resolve(ip("10.0.0.1"), &spoofed), ip("10.0.0.1"));
+}
+
+#[test]
+fn trusted_peer_forwards_the_client_address() {
+    let trusted = proxies(&["10.0.0.0/8", "192.168.1.1"]);
+
+    let xff = headers(&[("x-forwarded-for", "198.51.100.7, 10.1.2.3")]);
+    assert_eq!(trusted.resolve(ip("192.168.1.1"), &xff), ip("198.51.100.7"));
+
+    let forwarded = headers(&[("forwarded", r#"for="[2001:db8::1]:4711", for=10.1.2.3;proto=https"#)]);
+    assert_eq!(trusted.resolve(ip("10.0.0.1"), &forwarded), ip("2001:db8::1"));
+
+    assert_eq!(trusted.resolve(ip("10.0.0.1"), &HeaderMap::new()), ip("10.0.0.1"));
+}
+
+#[test]
+fn client_prepended_hops_are_ignored() {
+    // The client sent its own X-Forwarded-For; the proxy appended the real address
+    let trusted = proxies(&["10.0.0.0/8"]);
+    let appended = headers(&[("x-forwarded-for", "1.1.1.1, 198.51.100.7")]);
+
+    assert_eq!(trusted.resolve(ip("10.0.0.1"), &appended), ip("198.51.100.7"));
+}
+
+#[test]
+fn invalid_cidrs_are_rejected() {
+    assert!("10.0.0.0/33".parse::<Cidr>().is_err());
+    assert!("not-an-ip".parse::<Cidr>().is_err());
+    assert!("::1/128".parse::<Cidr>().is_ok());
+}
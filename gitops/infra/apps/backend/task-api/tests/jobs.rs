@@ -0,0 +1,79 @@
+use async_trait::async_trait;
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use task_api::jobs::{Job, Jobs};
+
+struct Counter {
+    ticks: Arc<AtomicUsize>,
+    stopped: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Job for Counter {
+    fn name(&self) -> &'static str {
+        "counter"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_millis(10)
+    }
+
+    async fn tick(&mut self) {
+        self.ticks.fetch_add(1, Ordering::SeqCst);
+    }
+
+    async fn stop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
+
+// Never finishes its stop hook, so shutdown has to abort it
+struct Stuck;
+
+#[async_trait]
+impl Job for Stuck {
+    fn name(&self) -> &'static str {
+        "stuck"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    async fn tick(&mut self) {}
+
+    async fn stop(&mut self) {
+        std::future::pending::<()>().await;
+    }
+}
+
+#[tokio::test]
+async fn jobs_tick_and_stop_on_shutdown() {
+    let ticks = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let mut jobs = Jobs::new();
+    jobs.register(Counter { ticks: ticks.clone(), stopped: stopped.clone() });
+    assert_eq!(jobs.len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    jobs.shutdown(Duration::from_secs(1)).await;
+
+    assert!(ticks.load(Ordering::SeqCst) >= 1);
+    assert!(stopped.load(Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn shutdown_aborts_jobs_that_overrun_the_timeout() {
+    let mut jobs = Jobs::new();
+    jobs.register(Stuck);
+
+    let started = std::time::Instant::now();
+    jobs.shutdown(Duration::from_millis(50)).await;
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
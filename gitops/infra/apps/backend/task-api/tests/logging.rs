@@ -0,0 +1,210 @@
+use axum::http::StatusCode;
+use std::{
+    collections::HashMap,
+    fs, io,
+    sync::{Arc, Mutex},
+};
+use task_api::models::{
+    logfmt::{Logfmt, LogfmtFields},
+    logging::{
+        is_health_check, prune_log_files, set_base_path, LogFormat, LogRotation, LogSampler, LogSampling,
+        LoggingConfig, QuietPaths,
+    },
+};
+
+#[test]
+fn samples_one_in_n_successful_responses() {
+    let sampler = LogSampler::new(LogSampling { success_every: 10, redirect_every: 1 });
+
+    let logged = (0..1000).filter(|_| sampler.should_log(StatusCode::OK)).count();
+
+    assert_eq!(logged, 100);
+}
+
+#[test]
+fn always_logs_client_and_server_errors() {
+    let sampler = LogSampler::new(LogSampling { success_every: 1000, redirect_every: 1000 });
+
+    assert!((0..100).all(|_| sampler.should_log(StatusCode::NOT_FOUND)));
+    assert!((0..100).all(|_| sampler.should_log(StatusCode::INTERNAL_SERVER_ERROR)));
+}
+
+#[test]
+fn status_classes_are_sampled_independently() {
+    let sampler = LogSampler::new(LogSampling { success_every: 2, redirect_every: 5 });
+
+    let success = (0..10).filter(|_| sampler.should_log(StatusCode::CREATED)).count();
+    let redirect = (0..10).filter(|_| sampler.should_log(StatusCode::FOUND)).count();
+
+    assert_eq!(success, 5);
+    assert_eq!(redirect, 2);
+}
+
+#[test]
+fn quiet_paths_log_at_debug_unless_server_error() {
+    let quiet = QuietPaths::parse("/api/health, /api/health/ready,");
+
+    assert!(quiet.is_quiet("/api/health", StatusCode::OK));
+    assert!(!quiet.is_quiet("/api/health/ready", StatusCode::SERVICE_UNAVAILABLE));
+    assert!(quiet.is_quiet("/api/health", StatusCode::NOT_FOUND));
+    assert!(!quiet.is_quiet("/api/tasks", StatusCode::OK));
+}
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn logfmt_renders_one_key_value_line_per_event() {
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .fmt_fields(LogfmtFields)
+        .event_format(Logfmt)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::error_span!("user", user_id = "42", role = %"admin");
+        let _entered = span.enter();
+        tracing::warn!(task_id = 7, ok = true, name = "say \"hi\"\nthere", "Task not found");
+    });
+
+    let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    let (timestamp, rest) = output.split_once(' ').unwrap();
+    assert!(timestamp.starts_with("timestamp=") && timestamp.ends_with('Z'), "{}", timestamp);
+    assert_eq!(
+        rest,
+        "level=WARN target=logging message=\"Task not found\" task_id=7 ok=true \
+         name=\"say \\\"hi\\\"\\nthere\" span=user user_id=42 role=admin\n"
+    );
+}
+
+#[test]
+fn retention_keeps_the_newest_rotated_files() {
+    let dir = std::env::temp_dir().join(format!("task-api-logs-{}", uuid::Uuid::new_v4()));
+    fs::create_dir(&dir).unwrap();
+    for name in [
+        "task-api.log.2026-10-14",
+        "task-api.log.2026-10-16",
+        "task-api.log.2026-10-15",
+        "task-api.log.2026-10-16-09",
+        // The current file of `never` rotation and anything else in the directory stay
+        "task-api.log",
+        "task-api.log.2026-10-01.gz",
+        "other.log.2026-10-01",
+    ] {
+        fs::write(dir.join(name), "").unwrap();
+    }
+
+    let mut removed: Vec<String> = prune_log_files(&dir, "task-api.log", 2)
+        .unwrap()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    removed.sort();
+    assert_eq!(removed, ["task-api.log.2026-10-14", "task-api.log.2026-10-15"]);
+
+    let mut left: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    left.sort();
+    assert_eq!(
+        left,
+        [
+            "other.log.2026-10-01",
+            "task-api.log",
+            "task-api.log.2026-10-01.gz",
+            "task-api.log.2026-10-16",
+            "task-api.log.2026-10-16-09",
+        ]
+    );
+    assert!(prune_log_files(&dir, "task-api.log", 2).unwrap().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn only_the_probe_routes_are_health_checks() {
+    assert!(is_health_check("/api/health"));
+    assert!(is_health_check("/api/health/ready"));
+    assert!(!is_health_check("/api/health/"));
+    assert!(!is_health_check("/api/tasks"));
+}
+
+// Every test that sets the base path uses this one, since it can only be set once per process
+const BASE_PATH: &str = "/task-api";
+
+#[test]
+fn health_checks_match_under_the_base_path() {
+    set_base_path(BASE_PATH);
+
+    assert!(is_health_check("/task-api/api/health"));
+    assert!(is_health_check("/task-api/api/health/ready"));
+    assert!(!is_health_check("/task-api-v2/api/health"));
+    assert!(!is_health_check("/task-api/api/tasks"));
+}
+
+#[test]
+fn quiet_paths_match_under_the_base_path() {
+    set_base_path(BASE_PATH);
+
+    // Configured either way, matched against the nested route's full path
+    let quiet = QuietPaths::parse("/api/metrics, /task-api/api/health");
+    assert!(quiet.is_quiet("/task-api/api/metrics", StatusCode::OK));
+    assert!(quiet.is_quiet("/task-api/api/health", StatusCode::OK));
+    assert!(!quiet.is_quiet("/task-api/api/tasks", StatusCode::OK));
+    assert!(!quiet.is_quiet("/task-api/api/metrics", StatusCode::INTERNAL_SERVER_ERROR));
+}
+
+fn logging_config(vars: &[(&str, &str)]) -> (LoggingConfig, Vec<String>) {
+    let vars: HashMap<String, String> = vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    LoggingConfig::from_vars(|key| vars.get(key).cloned())
+}
+
+#[test]
+fn invalid_format_rotation_and_max_files_are_reported() {
+    let (config, problems) = logging_config(&[("LOG_FORMAT", "jsno"), ("LOG_ROTATION", "weekly"), ("LOG_MAX_FILES", "0")]);
+
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems[0].contains("LOG_FORMAT") && problems[0].contains("'jsno'"));
+    assert!(problems[1].contains("LOG_ROTATION") && problems[1].contains("'weekly'"));
+    assert!(problems[2].contains("LOG_MAX_FILES") && problems[2].contains("'0'"));
+    assert_eq!(config.format, LogFormat::Pretty);
+    assert_eq!(config.rotation, LogRotation::Daily);
+    assert_eq!(config.max_files, None);
+
+    let (config, problems) = logging_config(&[("LOG_FORMAT", "logfmt"), ("LOG_ROTATION", "Hourly"), ("LOG_MAX_FILES", "7")]);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!(config.format, LogFormat::Logfmt);
+    assert_eq!(config.rotation, LogRotation::Hourly);
+    assert_eq!(config.max_files, Some(7));
+
+    let (_, problems) = logging_config(&[("LOG_FORMAT", ""), ("LOG_MAX_FILES", " ")]);
+    assert!(problems.is_empty(), "blank values count as unset: {:?}", problems);
+}
+
+#[test]
+fn sample_rates_must_be_positive_whole_numbers() {
+    let (config, problems) = logging_config(&[("LOG_SAMPLE_2XX", "0.1"), ("LOG_SAMPLE_3XX", "0")]);
+
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems[0].contains("LOG_SAMPLE_2XX") && problems[0].contains("'0.1'"));
+    assert!(problems[1].contains("LOG_SAMPLE_3XX") && problems[1].contains("'0'"));
+    assert_eq!((config.sampling.success_every, config.sampling.redirect_every), (1, 1));
+
+    let (config, problems) = logging_config(&[("LOG_SAMPLE_2XX", "10")]);
+    assert!(problems.is_empty(), "{:?}", problems);
+    assert_eq!((config.sampling.success_every, config.sampling.redirect_every), (10, 1));
+}
@@ -0,0 +1,117 @@
+use task_api::queries::{self, TaskSort};
+
+#[test]
+fn list_sorts_map_to_fixed_statements() {
+    assert_eq!(
+        queries::list_tasks(None),
+        Some("SELECT * FROM tasks WHERE user_id = $1 AND tenant_id = $2")
+    );
+    assert_eq!(
+        queries::list_tasks(Some("created_at")),
+        Some("SELECT * FROM tasks WHERE user_id = $1 AND tenant_id = $2 ORDER BY created_at")
+    );
+    assert_eq!(queries::list_tasks(Some("position")), Some(queries::TASKS_BY_POSITION));
+    assert_eq!(queries::list_tasks(Some("created_at; DROP TABLE tasks")), None);
+}
+
+#[test]
+fn search_statements_share_one_where_clause() {
+    let filters = " WHERE user_id = $1 AND tenant_id = $2 \
+        AND ($3::text IS NULL OR name ILIKE $3 OR description ILIKE $3) \
+        AND ($4::timestamptz IS NULL OR created_at >= $4) \
+        AND ($5::timestamptz IS NULL OR created_at <= $5)";
+
+    assert_eq!(queries::SEARCH_TASK_COUNT, format!("SELECT COUNT(*) FROM tasks{}", filters));
+    assert_eq!(
+        TaskSort::Name.search_sql(),
+        format!("SELECT * FROM tasks{} ORDER BY name, created_at LIMIT $6 OFFSET $7", filters)
+    );
+    assert_eq!(
+        TaskSort::default().search_sql(),
+        format!("SELECT * FROM tasks{} ORDER BY created_at DESC, id LIMIT $6 OFFSET $7", filters)
+    );
+}
+
+#[test]
+fn every_sort_round_trips_through_its_name() {
+    for sort in [TaskSort::CreatedAt, TaskSort::UpdatedAt, TaskSort::Position, TaskSort::Name] {
+        assert_eq!(sort.as_str().parse(), Ok(sort));
+        assert!(sort.search_sql().ends_with("LIMIT $6 OFFSET $7"));
+    }
+    assert!("Name".parse::<TaskSort>().is_err());
+}
+
+#[test]
+fn admin_page_filters_on_an_optional_user() {
+    assert_eq!(
+        queries::ADMIN_TASK_COUNT,
+        "SELECT COUNT(*) FROM tasks WHERE tenant_id = $1 AND ($2::uuid IS NULL OR user_id = $2)"
+    );
+    assert!(queries::ADMIN_TASK_PAGE.starts_with(
+        "SELECT * FROM tasks WHERE tenant_id = $1 AND ($2::uuid IS NULL OR user_id = $2) ORDER BY created_at DESC, id"
+    ));
+}
+
+#[test]
+fn owned_task_statements_are_scoped_to_user_and_tenant() {
+    assert_eq!(
+        queries::OWNED_TASK,
+        "SELECT * FROM tasks WHERE id = $1 AND user_id = $2 AND tenant_id = $3"
+    );
+    assert_eq!(
+        queries::DELETE_OWNED_TASK,
+        "DELETE FROM tasks WHERE id = $1 AND user_id = $2 AND tenant_id = $3 RETURNING id"
+    );
+}
+
+#[test]
+fn create_and_import_share_one_insert() {
+    assert_eq!(
+        queries::INSERT_TASK,
+        "INSERT INTO tasks (name, description, user_id, tenant_id, position, client_id, created_at, updated_at) \
+         VALUES ($1, $2, $3, $4, \
+         COALESCE((SELECT MAX(position) FROM tasks WHERE user_id = $3 AND tenant_id = $4), 0) + 1, \
+         $5, NOW(), NOW()) RETURNING *"
+    );
+}
+
+#[test]
+fn user_scoped_statements_bind_user_then_tenant() {
+    for sql in [
+        queries::RECENT_DUPLICATE_EXISTS,
+        queries::LOCK_OWNED_TASKS,
+        queries::REORDER_TASKS,
+    ] {
+        assert!(sql.contains("WHERE user_id = $1 AND tenant_id = $2"), "{}", sql);
+    }
+    assert!(queries::LOCK_OWNED_TASKS.ends_with("AND id = ANY($3) FOR UPDATE"));
+    assert!(queries::REORDER_TASKS.contains("ORDER BY array_position($3, id) NULLS LAST, position, created_at"));
+    assert!(queries::RECENT_DUPLICATE_EXISTS.contains("name = $3 AND created_at > NOW() - make_interval(mins => $4)"));
+}
+
+#[test]
+fn admin_and_user_cleanup_deletes() {
+    assert_eq!(
+        queries::ADMIN_DELETE_TASK,
+        "DELETE FROM tasks WHERE id = $1 AND tenant_id = $2 RETURNING user_id"
+    );
+    assert_eq!(queries::DELETE_USER_TASKS, "DELETE FROM tasks WHERE user_id = $1");
+}
+
+#[test]
+fn settings_are_read_and_upserted_by_key() {
+    assert_eq!(queries::SETTING, "SELECT value FROM settings WHERE key = $1");
+    assert_eq!(
+        queries::UPSERT_SETTING,
+        "INSERT INTO settings (key, value, updated_at) VALUES ($1, $2, NOW()) \
+         ON CONFLICT (key) DO UPDATE SET value = EXCLUDED.value, updated_at = NOW()"
+    );
+}
+
+#[test]
+fn user_task_lock_is_keyed_by_user_and_tenant() {
+    assert_eq!(
+        queries::LOCK_USER_TASKS,
+        "SELECT pg_advisory_xact_lock(hashtextextended($1::text || '/' || $2, 0))"
+    );
+}
@@ -0,0 +1,18 @@
+use serde_json::json;
+use task_api::models::role::Role;
+
+#[test]
+fn quoted_json_role_falls_back_to_user() {
+    // `Value::to_string` keeps the JSON quotes, which never match a role name
+    let quoted = json!("admin").to_string();
+    assert_eq!(quoted, "\"admin\"");
+    assert_eq!(Role::from(quoted), Role::User);
+}
+
+#[test]
+fn unquoted_json_role_is_parsed() {
+    let value = json!("admin");
+    let role = Role::from(value.as_str().unwrap().to_string());
+    assert_eq!(role, Role::Admin);
+    assert_eq!(Role::from("USER".to_string()), Role::User);
+}
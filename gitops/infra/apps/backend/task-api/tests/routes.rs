@@ -0,0 +1,237 @@
+mod common;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Method, StatusCode},
+    routing::get,
+    Router,
+};
+use axum_keycloak_auth::instance::{KeycloakAuthInstance, KeycloakConfig};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::Notify;
+use task_api::keycloak::MockKeycloakClient;
+use task_api::models::state::AppState;
+use task_api::models::config::BasicAuthCredentials;
+use task_api::routes::{create_routes, docs_routes, shed_load, POSTMAN_PATH};
+use tower::ServiceExt;
+
+async fn send(method: Method, uri: &str) -> (StatusCode, Option<String>, Value) {
+    send_body(method, uri, None).await
+}
+
+fn app(state: Arc<AppState>) -> Router {
+    let keycloak_config = KeycloakConfig::builder()
+        .server(Url::parse(&state.config.keycloak_url).unwrap())
+        .realm(state.config.realm.clone())
+        .build();
+    create_routes(state, Arc::new(KeycloakAuthInstance::new(keycloak_config)))
+}
+
+async fn send_body(method: Method, uri: &str, body: Option<(&str, &str)>) -> (StatusCode, Option<String>, Value) {
+    let app = app(common::test_state(Arc::new(MockKeycloakClient::new())));
+
+    let mut builder = Request::builder().method(method).uri(uri);
+    let body = match body {
+        Some((content_type, body)) => {
+            builder = builder.header(header::CONTENT_TYPE, content_type);
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .oneshot(builder.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .map(|v| v.to_str().unwrap().to_string());
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, content_type, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn unknown_route_returns_json_404() {
+    let (status, content_type, body) = send(Method::GET, "/api/nope").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(content_type.as_deref(), Some("application/json"));
+    assert_eq!(body, json!({"status": "fail", "error": "Not Found"}));
+}
+
+#[tokio::test]
+async fn wrong_method_returns_json_405() {
+    let (status, content_type, body) = send(Method::POST, "/api/health").await;
+
+    assert_eq!(status, StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(content_type.as_deref(), Some("application/json"));
+    assert_eq!(body, json!({"status": "fail", "error": "Method Not Allowed"}));
+}
+
+#[tokio::test]
+async fn head_is_answered_like_get_without_a_body() {
+    let (status, content_type, body) = send(Method::HEAD, "/api/health").await;
+
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type.as_deref(), Some("application/json"));
+    assert_eq!(body, Value::Null);
+}
+
+#[tokio::test]
+async fn health_includes_the_configured_fields() {
+    let mut state = (*common::test_state(Arc::new(MockKeycloakClient::new()))).clone();
+    state.config.health_fields = vec![
+        ("service".to_string(), "task-api".to_string()),
+        ("env".to_string(), "prod".to_string()),
+    ];
+    let request = Request::builder().uri("/api/health").body(Body::empty()).unwrap();
+
+    let response = app(Arc::new(state)).oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body, json!({"status": "Active", "service": "task-api", "env": "prod"}));
+}
+
+#[tokio::test]
+async fn unsupported_content_encoding_returns_415() {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/tasks")
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_ENCODING, "br")
+        .body(Body::from("not brotli"))
+        .unwrap();
+    let response = app(common::test_state(Arc::new(MockKeycloakClient::new())))
+        .oneshot(request)
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["status"], "fail");
+}
+
+#[tokio::test]
+async fn non_json_body_returns_415() {
+    let (status, _, body) = send_body(Method::POST, "/api/tasks", Some(("text/plain", "buy milk"))).await;
+
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(body["error"], "Content-Type must be application/json");
+}
+
+#[tokio::test]
+async fn unauthenticated_preflight_is_answered_by_cors() {
+    let mut state = (*common::test_state(Arc::new(MockKeycloakClient::new()))).clone();
+    state.config.cors_allowed_origins = vec!["https://app.example.com".to_string()];
+
+    let request = Request::builder()
+        .method(Method::OPTIONS)
+        .uri("/api/tasks")
+        .header(header::ORIGIN, "https://app.example.com")
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization,content-type")
+        .body(Body::empty())
+        .unwrap();
+    let response = app(Arc::new(state)).oneshot(request).await.unwrap();
+
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let headers = response.headers();
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+    let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap();
+    assert!(methods.contains("POST"), "{}", methods);
+    let allowed = headers[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str().unwrap();
+    assert!(allowed.contains("authorization"), "{}", allowed);
+}
+
+async fn docs_status(docs: &Router, authorization: Option<&str>) -> (StatusCode, Option<String>) {
+    docs_status_at(docs, "/api-docs/openapi.json", authorization).await
+}
+
+async fn docs_status_at(docs: &Router, uri: &str, authorization: Option<&str>) -> (StatusCode, Option<String>) {
+    let mut builder = Request::builder().uri(uri);
+    if let Some(authorization) = authorization {
+        builder = builder.header(header::AUTHORIZATION, authorization);
+    }
+    let response = docs.clone().oneshot(builder.body(Body::empty()).unwrap()).await.unwrap();
+    let challenge = response
+        .headers()
+        .get(header::WWW_AUTHENTICATE)
+        .map(|v| v.to_str().unwrap().to_string());
+    (response.status(), challenge)
+}
+
+#[tokio::test]
+async fn docs_require_basic_auth_when_configured() {
+    let mut config = common::test_config();
+    config.docs_basic_auth = Some(BasicAuthCredentials {
+        username: "docs".to_string(),
+        password: "s3cret".to_string(),
+    });
+    let docs = docs_routes(&config, utoipa::openapi::OpenApiBuilder::new().build());
+
+    let (status, challenge) = docs_status(&docs, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(challenge.unwrap().starts_with("Basic realm="));
+
+    // "docs:wrong" and "docs:s3cret"
+    let (status, _) = docs_status(&docs, Some("Basic ZG9jczp3cm9uZw==")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = docs_status(&docs, Some("Basic ZG9jczpzM2NyZXQ=")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = docs_status_at(&docs, POSTMAN_PATH, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn docs_are_open_by_default_and_404_when_disabled() {
+    let mut config = common::test_config();
+    let docs = docs_routes(&config, utoipa::openapi::OpenApiBuilder::new().build());
+    assert_eq!(docs_status(&docs, None).await.0, StatusCode::OK);
+    assert_eq!(docs_status_at(&docs, POSTMAN_PATH, None).await.0, StatusCode::OK);
+
+    config.swagger_enabled = false;
+    let docs = docs_routes(&config, utoipa::openapi::OpenApiBuilder::new().build());
+    assert_eq!(docs_status(&docs, None).await.0, StatusCode::NOT_FOUND);
+    assert_eq!(docs_status_at(&docs, POSTMAN_PATH, None).await.0, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn requests_past_the_concurrency_limit_are_shed() {
+    let (entered, release) = (Arc::new(Notify::new()), Arc::new(Notify::new()));
+    let slow = {
+        let (entered, release) = (entered.clone(), release.clone());
+        move || async move {
+            entered.notify_one();
+            release.notified().await;
+            "done"
+        }
+    };
+    let app = shed_load(
+        Router::new().route("/slow", get(slow)).route("/fast", get(|| async { "done" })),
+        1,
+    );
+    let request = |uri| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+    let first = tokio::spawn(app.clone().oneshot(request("/slow")));
+    entered.notified().await;
+
+    // The limit spans routes, not just the one that is busy
+    let shed = app.clone().oneshot(request("/fast")).await.unwrap();
+    assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(shed.headers()[header::RETRY_AFTER], "1");
+    let body = axum::body::to_bytes(shed.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["status"], "fail");
+
+    release.notify_one();
+    assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+}
@@ -0,0 +1,98 @@
+mod common;
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+use std::sync::Arc;
+use task_api::handlers::session::{list_sessions, revoke_session};
+use task_api::keycloak::MockKeycloakClient;
+use task_api::models::{response::Success, role::Role};
+use uuid::Uuid;
+
+const ALICE: Uuid = Uuid::from_u128(0xa11ce);
+const BOB: Uuid = Uuid::from_u128(0xb0b);
+
+fn mock_with_sessions() -> Arc<MockKeycloakClient> {
+    Arc::new(
+        MockKeycloakClient::new()
+            .with_session(
+                ALICE,
+                json!({
+                    "id": "alice-session",
+                    "ipAddress": "10.0.0.7",
+                    "start": 1_700_000_000_000i64,
+                    "lastAccess": 1_700_000_060_000i64,
+                    "clients": {"3f1c": "task-web"}
+                }),
+            )
+            .with_session(BOB, json!({"id": "bob-session"})),
+    )
+}
+
+#[tokio::test]
+async fn lists_only_the_callers_sessions() {
+    let state = common::test_state(mock_with_sessions());
+
+    let Ok(Json(Success { data: sessions, .. })) =
+        list_sessions(Extension(common::test_token(ALICE, &[Role::User])), State(state)).await
+    else {
+        panic!("list_sessions failed");
+    };
+
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].id, "alice-session");
+    assert_eq!(sessions[0].ip_address.as_deref(), Some("10.0.0.7"));
+    assert_eq!(sessions[0].clients, ["task-web"]);
+    assert!(sessions[0].last_access_at > sessions[0].started_at);
+}
+
+#[tokio::test]
+async fn user_without_sessions_gets_an_empty_list() {
+    let state = common::test_state(mock_with_sessions());
+
+    let Ok(Json(Success { data: sessions, .. })) =
+        list_sessions(Extension(common::test_token(Uuid::new_v4(), &[Role::User])), State(state)).await
+    else {
+        panic!("list_sessions failed");
+    };
+
+    assert!(sessions.is_empty());
+}
+
+#[tokio::test]
+async fn revokes_own_session() {
+    let keycloak = mock_with_sessions();
+    let state = common::test_state(keycloak.clone());
+
+    let response = revoke_session(
+        Extension(common::test_token(ALICE, &[Role::User])),
+        State(state),
+        Path("alice-session".to_string()),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(keycloak.session_ids(), ["bob-session"]);
+}
+
+#[tokio::test]
+async fn cannot_revoke_another_users_session() {
+    let keycloak = mock_with_sessions();
+    let state = common::test_state(keycloak.clone());
+
+    let response = revoke_session(
+        Extension(common::test_token(ALICE, &[Role::User])),
+        State(state),
+        Path("bob-session".to_string()),
+    )
+    .await
+    .into_response();
+
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(keycloak.session_ids(), ["alice-session", "bob-session"]);
+}
@@ -0,0 +1,388 @@
+//! End-to-end task flows against a real Postgres started with testcontainers.
+//!
+//! These need a Docker daemon and are ignored by default:
+//! `cargo test --test tasks_db -- --ignored`
+
+mod common;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post, put},
+    Router,
+};
+use axum_keycloak_auth::decode::RawClaims;
+use serde_json::{json, Value};
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+use task_api::handlers::{
+    admin::{admin_stats, set_maintenance},
+    export::export_tasks,
+    ownership::{fetch_owned_task, Tenant, DEFAULT_TENANT},
+    task::{create_task, delete_task, list_tasks},
+    transaction::transaction,
+};
+use task_api::jobs::Job;
+use task_api::maintenance::MaintenanceSync;
+use task_api::models::{role::Role, state::AppState};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+const SUBJECT_HEADER: &str = "x-test-subject";
+const TENANT_HEADER: &str = "x-test-tenant";
+
+// Stands in for the Keycloak layer: turns a subject header into a decoded token
+async fn inject_token(mut req: Request, next: Next) -> Response {
+    let subject = req
+        .headers()
+        .get(SUBJECT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| Uuid::parse_str(v).ok())
+        .expect("tests must send a subject header");
+    let tenant = req
+        .headers()
+        .get(TENANT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| Value::from(v.to_string()));
+    req.extensions_mut()
+        .insert(common::test_token(subject, &[Role::User]));
+    if let Some(tenant) = tenant {
+        let claims: RawClaims = [("tenant_id".to_string(), tenant)].into_iter().collect();
+        req.extensions_mut().insert(claims);
+    }
+    next.run(req).await
+}
+
+fn task_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/api/tasks",
+            post(create_task)
+                .layer(middleware::from_fn_with_state(state.clone(), transaction))
+                .get(list_tasks),
+        )
+        .route("/api/tasks/export", get(export_tasks))
+        .route("/api/tasks/{id}", delete(delete_task))
+        .route("/api/admin/stats", get(admin_stats))
+        .route("/api/admin/maintenance", put(set_maintenance))
+        .layer(middleware::from_fn(inject_token))
+        .with_state(state)
+}
+
+async fn send(app: &Router, method: Method, uri: &str, user: Uuid, body: Option<Value>) -> (StatusCode, Value) {
+    send_as(app, method, uri, user, None, body).await
+}
+
+async fn send_as(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    user: Uuid,
+    tenant: Option<&str>,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(SUBJECT_HEADER, user.to_string());
+    if let Some(tenant) = tenant {
+        builder = builder.header(TENANT_HEADER, tenant);
+    }
+    let body = match body {
+        Some(json) => {
+            builder = builder.header(header::CONTENT_TYPE, "application/json");
+            Body::from(json.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(builder.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json = if bytes.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&bytes).unwrap()
+    };
+    (status, json)
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn create_list_delete_task_flow() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db));
+    let alice = Uuid::new_v4();
+
+    let (status, created) = send(
+        &app,
+        Method::POST,
+        "/api/tasks",
+        alice,
+        Some(json!({"name": "Write tests", "description": "with testcontainers"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["data"]["name"], "Write tests");
+    assert_eq!(created["data"]["user_id"], alice.to_string());
+    let task_id = created["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, listed) = send(&app, Method::GET, "/api/tasks", alice, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["data"]["total"], 1);
+    assert_eq!(listed["data"]["tasks"][0]["id"], task_id);
+
+    let uri = format!("/api/tasks/{}", task_id);
+    let (status, _) = send(&app, Method::DELETE, &uri, alice, None).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&app, Method::DELETE, &uri, alice, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, listed) = send(&app, Method::GET, "/api/tasks", alice, None).await;
+    assert_eq!(listed["data"]["total"], 0);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn client_id_is_echoed_back() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db));
+    let alice = Uuid::new_v4();
+
+    let body = json!({"name": "Offline draft", "client_id": " local-42 "});
+    let (status, created) = send(&app, Method::POST, "/api/tasks", alice, Some(body)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(created["data"]["client_id"], "local-42");
+
+    let (_, listed) = send(&app, Method::GET, "/api/tasks", alice, None).await;
+    assert_eq!(listed["data"]["tasks"][0]["client_id"], "local-42");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn delete_answers_200_with_a_body_when_configured() {
+    let (_container, db) = common::start_postgres().await;
+    let mut state = (*common::db_state(db)).clone();
+    state.config.delete_returns_body = true;
+    let app = task_router(Arc::new(state));
+    let alice = Uuid::new_v4();
+
+    let (_, created) = send(&app, Method::POST, "/api/tasks", alice, Some(json!({"name": "Short-lived"}))).await;
+    let uri = format!("/api/tasks/{}", created["data"]["id"].as_str().unwrap());
+
+    let (status, body) = send(&app, Method::DELETE, &uri, alice, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({"status": "success"}));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn tasks_are_scoped_to_their_owner() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db));
+    let alice = Uuid::new_v4();
+    let bob = Uuid::new_v4();
+
+    let (_, created) = send(&app, Method::POST, "/api/tasks", alice, Some(json!({"name": "Alice's task"}))).await;
+    let task_id = created["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, listed) = send(&app, Method::GET, "/api/tasks", bob, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["data"]["total"], 0);
+
+    let uri = format!("/api/tasks/{}", task_id);
+    let (status, _) = send(&app, Method::DELETE, &uri, bob, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, listed) = send(&app, Method::GET, "/api/tasks", alice, None).await;
+    assert_eq!(listed["data"]["total"], 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn tasks_are_scoped_to_their_tenant() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db));
+    // The same user id in two tenants must still see two separate task lists
+    let user = Uuid::new_v4();
+
+    let (status, created) = send_as(
+        &app,
+        Method::POST,
+        "/api/tasks",
+        user,
+        Some("acme"),
+        Some(json!({"name": "Acme task"})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let task_id = created["data"]["id"].as_str().unwrap().to_string();
+
+    let (_, listed) = send_as(&app, Method::GET, "/api/tasks", user, Some("globex"), None).await;
+    assert_eq!(listed["data"]["total"], 0);
+    let (_, listed) = send(&app, Method::GET, "/api/tasks", user, None).await;
+    assert_eq!(listed["data"]["total"], 0);
+
+    let uri = format!("/api/tasks/{}", task_id);
+    let (status, _) = send_as(&app, Method::DELETE, &uri, user, Some("globex"), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (_, listed) = send_as(&app, Method::GET, "/api/tasks", user, Some("acme"), None).await;
+    assert_eq!(listed["data"]["total"], 1);
+    assert_eq!(listed["data"]["tasks"][0]["id"], task_id);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn owned_task_lookup_is_scoped_to_user_and_tenant() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db.clone()));
+    let alice = Uuid::new_v4();
+
+    let (_, created) = send(&app, Method::POST, "/api/tasks", alice, Some(json!({"name": "Alice's task"}))).await;
+    let task_id = Uuid::parse_str(created["data"]["id"].as_str().unwrap()).unwrap();
+    let default_tenant = Tenant(DEFAULT_TENANT.to_string());
+
+    let Ok(task) = fetch_owned_task(&db, task_id, alice, &default_tenant).await else {
+        panic!("the owner should find their task");
+    };
+    assert_eq!(task.id, task_id);
+
+    for (user, tenant) in [(Uuid::new_v4(), default_tenant.clone()), (alice, Tenant("acme".to_string()))] {
+        let Err(e) = fetch_owned_task(&db, task_id, user, &tenant).await else {
+            panic!("{:?} must not see another owner's task", tenant);
+        };
+        assert_eq!(e.into_response().status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn admin_stats_count_only_the_admins_tenant() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db));
+    let user = Uuid::new_v4();
+
+    for (tenant, name) in [("acme", "Acme task"), ("acme", "Another"), ("globex", "Globex task")] {
+        let (status, _) =
+            send_as(&app, Method::POST, "/api/tasks", user, Some(tenant), Some(json!({"name": name}))).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let (status, stats) = send_as(&app, Method::GET, "/api/admin/stats", user, Some("acme"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(stats["data"]["total_tasks"], 2);
+    let (_, stats) = send_as(&app, Method::GET, "/api/admin/stats", user, Some("globex"), None).await;
+    assert_eq!(stats["data"]["total_tasks"], 1);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn maintenance_switch_reaches_every_replica() {
+    let (_container, db) = common::start_postgres().await;
+    let serving = common::db_state(db.clone());
+    let other = common::db_state(db.clone());
+    let mut sync = MaintenanceSync::new(db, other.maintenance.clone(), false, Duration::from_secs(5));
+    let app = task_router(serving.clone());
+    let admin = Uuid::new_v4();
+
+    let (status, body) =
+        send(&app, Method::PUT, "/api/admin/maintenance", admin, Some(json!({"enabled": true}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["enabled"], true);
+    assert!(serving.maintenance.load(Ordering::Relaxed));
+    assert!(!other.maintenance.load(Ordering::Relaxed));
+
+    sync.tick().await;
+    assert!(other.maintenance.load(Ordering::Relaxed));
+
+    send(&app, Method::PUT, "/api/admin/maintenance", admin, Some(json!({"enabled": false}))).await;
+    sync.tick().await;
+    assert!(!other.maintenance.load(Ordering::Relaxed));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn csv_export_quotes_fields_across_rows() {
+    let (_container, db) = common::start_postgres().await;
+    let app = task_router(common::db_state(db));
+    let user = Uuid::new_v4();
+    for (name, description, client_id) in
+        [("Plain", "one line", None), ("Comma, \"quoted\"", "two\nlines", Some("offline-1"))]
+    {
+        let body = json!({"name": name, "description": description, "client_id": client_id});
+        let (status, _) = send(&app, Method::POST, "/api/tasks", user, Some(body)).await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let request = Request::builder()
+        .uri("/api/tasks/export?format=csv")
+        .header(SUBJECT_HEADER, user.to_string())
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+    let mut reader = csv::Reader::from_reader(body.as_ref());
+    assert_eq!(
+        reader.headers().unwrap(),
+        vec!["id", "name", "description", "user_id", "position", "client_id", "created_at", "updated_at"]
+    );
+    let rows: Vec<csv::StringRecord> = reader.records().map(Result::unwrap).collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(&rows[0][1], "Plain");
+    assert_eq!(&rows[1][1], "Comma, \"quoted\"");
+    assert_eq!(&rows[1][2], "two\nlines");
+    assert_eq!(&rows[0][5], "");
+    assert_eq!(&rows[1][5], "offline-1");
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn concurrent_creates_respect_the_task_limit() {
+    let (_container, db) = common::start_postgres().await;
+    let mut state = (*common::db_state(db)).clone();
+    state.config.max_tasks_per_user = Some(1);
+    let app = task_router(Arc::new(state));
+    let user = Uuid::new_v4();
+
+    let statuses = futures_util::future::join_all((0..5).map(|i| {
+        let app = app.clone();
+        async move { send(&app, Method::POST, "/api/tasks", user, Some(json!({"name": format!("Task {}", i)}))).await.0 }
+    }))
+    .await;
+
+    assert_eq!(statuses.iter().filter(|s| **s == StatusCode::CREATED).count(), 1, "{:?}", statuses);
+    assert!(statuses.iter().all(|s| *s == StatusCode::CREATED || *s == StatusCode::CONFLICT));
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn concurrent_duplicates_insert_once() {
+    let (_container, db) = common::start_postgres().await;
+    let mut state = (*common::db_state(db)).clone();
+    state.config.duplicate_window_minutes = Some(5);
+    let app = task_router(Arc::new(state));
+    let user = Uuid::new_v4();
+
+    let statuses = futures_util::future::join_all((0..5).map(|_| {
+        let app = app.clone();
+        async move { send(&app, Method::POST, "/api/tasks", user, Some(json!({"name": "Same task"}))).await.0 }
+    }))
+    .await;
+
+    assert_eq!(statuses.iter().filter(|s| **s == StatusCode::CREATED).count(), 1, "{:?}", statuses);
+}
@@ -0,0 +1,343 @@
+//! Protected routes through the full router with the `test-auth` bypass.
+//!
+//! Only built with the feature: `cargo test --features test-auth --test test_auth`
+
+mod common;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, Method, StatusCode},
+    middleware,
+    routing::get as get_route,
+    Router,
+};
+use axum_keycloak_auth::instance::{KeycloakAuthInstance, KeycloakConfig};
+use reqwest::Url;
+use serde_json::{json, Value};
+use std::sync::{atomic::Ordering, Arc};
+use task_api::handlers::{
+    ownership::{Tenant, TENANT_MAX_CHARS},
+    test_auth::{inject_test_token, GROUPS_HEADER, PANIC_PATH, ROLES_HEADER, SUBJECT_HEADER, TENANT_HEADER},
+};
+use task_api::keycloak::MockKeycloakClient;
+use task_api::models::{role::Role, state::AppState};
+use task_api::routes::create_routes;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+fn app() -> Router {
+    let keycloak = Arc::new(MockKeycloakClient::new().with_user(
+        json!({"id": Uuid::new_v4().to_string(), "username": "alice", "email": "alice@example.com"}),
+        Role::User,
+    ));
+    router(common::test_state(keycloak))
+}
+
+fn router(state: Arc<AppState>) -> Router {
+    let keycloak_config = KeycloakConfig::builder()
+        .server(Url::parse(&state.config.keycloak_url).unwrap())
+        .realm(state.config.realm.clone())
+        .build();
+
+    create_routes(state, Arc::new(KeycloakAuthInstance::new(keycloak_config)))
+}
+
+async fn get(uri: &str, subject: Option<Uuid>, roles: Option<&str>) -> (StatusCode, Value) {
+    send(Method::GET, uri, subject, roles).await
+}
+
+async fn send(method: Method, uri: &str, subject: Option<Uuid>, roles: Option<&str>) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(subject) = subject {
+        builder = builder.header(SUBJECT_HEADER, subject.to_string());
+    }
+    if let Some(roles) = roles {
+        builder = builder.header(ROLES_HEADER, roles);
+    }
+
+    let response = app()
+        .oneshot(builder.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+#[tokio::test]
+async fn missing_subject_is_unauthorized() {
+    let (status, _) = get("/api/admin/users", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn validate_echoes_the_tokens_subject_and_expiry() {
+    let subject = Uuid::new_v4();
+    let (status, body) = get("/api/auth/validate", Some(subject), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["valid"], true);
+    assert_eq!(body["data"]["subject"], subject.to_string());
+    let expires_at: chrono::DateTime<chrono::Utc> = body["data"]["expires_at"].as_str().unwrap().parse().unwrap();
+    assert!(expires_at > chrono::Utc::now());
+
+    let (status, _) = get("/api/auth/validate", None, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn admin_routes_require_admin_role() {
+    let (status, _) = get("/api/admin/users", Some(Uuid::new_v4()), None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admin_task_list_requires_admin_role() {
+    let (status, _) = get("/api/admin/tasks", Some(Uuid::new_v4()), Some("user")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admin_user_tasks_requires_admin_role() {
+    let uri = format!("/api/admin/users/{}/tasks", Uuid::new_v4());
+    let (status, _) = get(&uri, Some(Uuid::new_v4()), Some("user")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn admin_task_delete_requires_admin_role() {
+    let uri = format!("/api/admin/tasks/{}", Uuid::new_v4());
+    let (status, _) = send(Method::DELETE, &uri, Some(Uuid::new_v4()), Some("user")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn malformed_task_id_returns_json_400() {
+    let (status, body) = send(Method::DELETE, "/api/tasks/not-a-uuid", Some(Uuid::new_v4()), None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body, json!({"status": "fail", "error": "invalid id"}));
+}
+
+#[tokio::test]
+async fn gzipped_json_body_is_decompressed() {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(br#"{"name": "   "}"#).unwrap();
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/tasks")
+        .header(SUBJECT_HEADER, Uuid::new_v4().to_string())
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::CONTENT_ENCODING, "gzip")
+        .body(Body::from(encoder.finish().unwrap()))
+        .unwrap();
+
+    // Reaching name validation proves the Json extractor saw the inflated body
+    let response = app().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(body["error"], "name is required");
+}
+
+#[tokio::test]
+async fn admin_role_reaches_admin_routes() {
+    let (status, users) = get("/api/admin/users", Some(Uuid::new_v4()), Some("user,admin")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(users["status"], "success");
+    assert_eq!(users["data"][0]["name"], "alice");
+}
+
+#[tokio::test]
+async fn admin_groups_require_membership_when_configured() {
+    let mut state = (*common::test_state(Arc::new(MockKeycloakClient::new()))).clone();
+    state.config.admin_groups = vec!["/admins".to_string()];
+    let app = router(Arc::new(state));
+    let status = |groups: Option<&'static str>| {
+        let app = app.clone();
+        async move {
+            let mut builder = Request::builder()
+                .uri("/api/admin/maintenance")
+                .header(SUBJECT_HEADER, Uuid::new_v4().to_string())
+                .header(ROLES_HEADER, "admin");
+            if let Some(groups) = groups {
+                builder = builder.header(GROUPS_HEADER, groups);
+            }
+            app.oneshot(builder.body(Body::empty()).unwrap()).await.unwrap().status()
+        }
+    };
+
+    assert_eq!(status(None).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(Some("/staff, /admins-old")).await, StatusCode::FORBIDDEN);
+    assert_eq!(status(Some("/staff, /admins")).await, StatusCode::OK);
+    // Subgroup membership, and the claim without full group paths
+    assert_eq!(status(Some("/admins/eu")).await, StatusCode::OK);
+    assert_eq!(status(Some("admins")).await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn maintenance_mode_gates_user_routes_only() {
+    // Switching it goes through the database, see tasks_db.rs; here the pod's flag is set directly
+    let state = common::test_state(Arc::new(MockKeycloakClient::new()));
+    let app = router(state.clone());
+    let request = |method: Method, uri: &str, roles: &str, body: Option<&str>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(SUBJECT_HEADER, Uuid::new_v4().to_string())
+            .header(ROLES_HEADER, roles);
+        match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        }
+        .unwrap()
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::PUT, "/api/admin/maintenance", "user", Some(r#"{"enabled": true}"#)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    state.maintenance.store(true, Ordering::Relaxed);
+
+    let response = app.clone().oneshot(request(Method::GET, "/api/tasks", "user", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["error"], "maintenance");
+
+    // Admin and health routes keep answering so the mode can be turned off again
+    let response = app.clone().oneshot(request(Method::GET, "/api/admin/maintenance", "admin", None)).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<Value>(&body).unwrap()["data"]["enabled"], true);
+    let response = app.clone().oneshot(request(Method::GET, "/api/health", "user", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    state.maintenance.store(false, Ordering::Relaxed);
+    // Rejected for its sort before any database access, but no longer by the gate
+    let response = app.oneshot(request(Method::GET, "/api/tasks?sort=bogus", "user", None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[derive(Clone, Default)]
+struct Capture(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn handler_logs_carry_the_callers_user_span() {
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_writer(move || writer.clone())
+        .finish();
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let subject = Uuid::new_v4();
+    let (status, _) = get("/api/auth/sessions", Some(subject), Some("user,admin")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    let line: Value = output
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .find(|line| line["fields"]["message"] == "Listing sessions from Keycloak")
+        .expect("handler log line");
+    assert_eq!(line["span"]["name"], "user");
+    assert_eq!(line["span"]["user_id"], subject.to_string());
+    assert_eq!(line["span"]["role"], "admin");
+}
+
+#[tokio::test]
+async fn handler_panics_become_500_with_the_request_id_logged() {
+    let capture = Capture::default();
+    let writer = capture.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .json()
+        .with_writer(move || writer.clone())
+        .finish();
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let (status, body) = get(PANIC_PATH, None, None).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body, json!({"status": "fail", "error": "Internal server error"}));
+
+    let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    let lines: Vec<Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let panic = lines
+        .iter()
+        .find(|line| line["fields"]["message"] == "Handler panicked")
+        .expect("panic log line");
+    assert_eq!(panic["fields"]["panic"], format!("deliberate panic from {}", PANIC_PATH));
+    let completed = lines
+        .iter()
+        .find(|line| line["fields"]["status"] == "500 Internal Server Error")
+        .expect("request log line");
+    assert_eq!(panic["fields"]["request_id"], completed["fields"]["request_id"]);
+}
+
+#[tokio::test]
+async fn tenant_header_sets_the_configured_tenant_claim() {
+    let mut state = (*common::test_state(Arc::new(MockKeycloakClient::new()))).clone();
+    state.config.tenant_claim = "org".to_string();
+    let state = Arc::new(state);
+    let app = Router::new()
+        .route("/tenant", get_route(|tenant: Tenant| async move { tenant.0 }))
+        .layer(middleware::from_fn_with_state(state.clone(), inject_test_token))
+        .with_state(state);
+
+    let request = Request::builder()
+        .uri("/tenant")
+        .header(SUBJECT_HEADER, Uuid::new_v4().to_string())
+        .header(TENANT_HEADER, "acme")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&bytes[..], b"acme");
+}
+
+#[tokio::test]
+async fn empty_and_overlong_tenant_claims_are_rejected() {
+    let state = common::test_state(Arc::new(MockKeycloakClient::new()));
+    let app = Router::new()
+        .route("/tenant", get_route(|tenant: Tenant| async move { tenant.0 }))
+        .layer(middleware::from_fn_with_state(state.clone(), inject_test_token))
+        .with_state(state);
+    let status = |tenant: String| {
+        let app = app.clone();
+        async move {
+            let request = Request::builder()
+                .uri("/tenant")
+                .header(SUBJECT_HEADER, Uuid::new_v4().to_string())
+                .header(TENANT_HEADER, tenant)
+                .body(Body::empty())
+                .unwrap();
+            app.oneshot(request).await.unwrap().status()
+        }
+    };
+
+    assert_eq!(status(String::new()).await, StatusCode::FORBIDDEN);
+    assert_eq!(status("   ".to_string()).await, StatusCode::FORBIDDEN);
+    assert_eq!(status("t".repeat(TENANT_MAX_CHARS + 1)).await, StatusCode::FORBIDDEN);
+    assert_eq!(status("t".repeat(TENANT_MAX_CHARS)).await, StatusCode::OK);
+}
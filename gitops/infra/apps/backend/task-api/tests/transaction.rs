@@ -0,0 +1,104 @@
+mod common;
+
+use axum::{
+    body::Body,
+    extract::{Path, Request},
+    http::StatusCode,
+    middleware,
+    routing::post,
+    Router,
+};
+use std::sync::Arc;
+use task_api::handlers::transaction::{transaction, Tx};
+use task_api::keycloak::MockKeycloakClient;
+use task_api::models::state::AppState;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+// Inserts a task, then answers with the status from the path
+async fn insert_then_respond(mut tx: Tx, Path((user_id, status)): Path<(Uuid, u16)>) -> StatusCode {
+    let Ok(conn) = tx.conn().await else {
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    };
+    sqlx::query("INSERT INTO tasks (name, user_id) VALUES ('tx test', $1)")
+        .bind(user_id)
+        .execute(conn)
+        .await
+        .unwrap();
+    StatusCode::from_u16(status).unwrap()
+}
+
+// Takes the transaction like a handler validating its body first, then rejects before any query
+async fn reject_before_using_tx(_tx: Tx) -> StatusCode {
+    StatusCode::BAD_REQUEST
+}
+
+async fn respond_without_tx() -> StatusCode {
+    StatusCode::OK
+}
+
+fn app(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/tx/{user_id}/{status}", post(insert_then_respond))
+        .route("/no-tx", post(respond_without_tx))
+        .route("/rejected", post(reject_before_using_tx))
+        .layer(middleware::from_fn_with_state(state.clone(), transaction))
+        .route("/unlayered/{user_id}/{status}", post(insert_then_respond))
+        .with_state(state)
+}
+
+async fn status(app: &Router, uri: &str) -> StatusCode {
+    app.clone()
+        .oneshot(Request::post(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn transaction_is_not_begun_unless_extracted() {
+    // The test state's pool points at a closed port, so any begin would fail
+    let app = app(common::test_state(Arc::new(MockKeycloakClient::new())));
+
+    assert_eq!(status(&app, "/no-tx").await, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn transaction_is_not_begun_until_first_used() {
+    let app = app(common::test_state(Arc::new(MockKeycloakClient::new())));
+
+    assert_eq!(status(&app, "/rejected").await, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn tx_without_the_middleware_is_a_500() {
+    let app = app(common::test_state(Arc::new(MockKeycloakClient::new())));
+
+    let uri = format!("/unlayered/{}/200", Uuid::new_v4());
+    assert_eq!(status(&app, &uri).await, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+#[ignore = "requires Docker"]
+async fn commits_on_success_and_rolls_back_on_error() {
+    let (_container, db) = common::start_postgres().await;
+    let app = app(common::db_state(db.clone()));
+    let count = |user: Uuid| {
+        let db = db.clone();
+        async move {
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM tasks WHERE user_id = $1")
+                .bind(user)
+                .fetch_one(&db)
+                .await
+                .unwrap()
+        }
+    };
+
+    let committed = Uuid::new_v4();
+    assert_eq!(status(&app, &format!("/tx/{}/201", committed)).await, StatusCode::CREATED);
+    assert_eq!(count(committed).await, 1);
+
+    let rolled_back = Uuid::new_v4();
+    assert_eq!(status(&app, &format!("/tx/{}/409", rolled_back)).await, StatusCode::CONFLICT);
+    assert_eq!(count(rolled_back).await, 0);
+}